@@ -0,0 +1,793 @@
+//! Command line option definitions, parsing and help output.
+
+use std::io::{self, Write};
+
+/// The description of a single command line option.
+pub struct OptionSpec {
+    /// The name used to look the option up, such as `--width` or `-c`.
+    pub name: &'static str,
+    /// An alternative spelling of the option, such as `-d` for `--delimiter`.
+    pub alias: Option<&'static str>,
+    /// The placeholder for the option's value, or `None` for a flag.
+    pub value: Option<&'static str>,
+    /// A one-line description shown by `--help`.
+    pub help: &'static str,
+}
+
+/// Every option accepted by tabbs, in the order they are listed by `--help`.
+pub const OPTIONS: &[OptionSpec] = &[
+    OptionSpec {
+        name: "-c",
+        alias: None,
+        value: Some("NAMES"),
+        help: "Comma-separated column names, with optional ::type and :l/:r/:c suffixes",
+    },
+    OptionSpec {
+        name: "--columns-file",
+        alias: None,
+        value: Some("FILE"),
+        help: "Read the column names from FILE instead of -c",
+    },
+    OptionSpec {
+        name: "-f",
+        alias: Some("--file"),
+        value: Some("FILE|URL"),
+        help: "Read input from a file, or an http:// URL (not https) with the `remote` feature",
+    },
+    OptionSpec {
+        name: "--input",
+        alias: None,
+        value: Some("csv|kv"),
+        help: "Input format: delimited records or `key: value` blocks",
+    },
+    OptionSpec {
+        name: "--lines",
+        alias: None,
+        value: Some("FIRST-LAST"),
+        help: "Only read this range of input lines, counting from 1",
+    },
+    OptionSpec {
+        name: "--fixed-widths",
+        alias: None,
+        value: Some("W1,W2,..."),
+        help: "Slice each input line into fields of these character widths",
+    },
+    OptionSpec {
+        name: "--delimiter",
+        alias: Some("-d"),
+        value: Some("CHAR"),
+        help: "Field delimiter; `\\t` for tabs or a space for runs of whitespace",
+    },
+    OptionSpec {
+        name: "--record-sep",
+        alias: None,
+        value: Some("CHAR"),
+        help: "Character ending each record instead of a newline, such as '\\0'",
+    },
+    OptionSpec {
+        name: "--self-describe",
+        alias: None,
+        value: None,
+        help: "Take the delimiter from a leading sep= line and the columns from the header",
+    },
+    OptionSpec {
+        name: "--stdin0",
+        alias: None,
+        value: None,
+        help: "Read NUL-separated fields, taking as many per row as there are columns",
+    },
+    OptionSpec {
+        name: "--comment",
+        alias: None,
+        value: Some("CHAR"),
+        help: "Skip lines starting with this character",
+    },
+    OptionSpec {
+        name: "--no-trim",
+        alias: None,
+        value: None,
+        help: "Keep whitespace around unquoted fields",
+    },
+    OptionSpec {
+        name: "--rtrim-for-width",
+        alias: None,
+        value: None,
+        help: "Size columns without counting the trailing whitespace of cells",
+    },
+    OptionSpec {
+        name: "--blank-as-empty",
+        alias: None,
+        value: None,
+        help: "Treat unquoted fields of only whitespace as empty, even with --no-trim",
+    },
+    OptionSpec {
+        name: "--backslash-escape",
+        alias: None,
+        value: None,
+        help: "Treat \\<delimiter> and \\\\ as literal characters",
+    },
+    OptionSpec {
+        name: "--sanitize-input",
+        alias: None,
+        value: None,
+        help: "Strip ANSI escapes and control characters from cells, even when not on a terminal",
+    },
+    OptionSpec {
+        name: "--no-sanitize-input",
+        alias: None,
+        value: None,
+        help: "Keep ANSI escapes and control characters in cells on a terminal",
+    },
+    OptionSpec {
+        name: "--diff",
+        alias: None,
+        value: Some("FILE"),
+        help: "Compare the input with FILE and mark added, removed and changed rows",
+    },
+    OptionSpec {
+        name: "--key",
+        alias: None,
+        value: Some("COLUMN"),
+        help: "The column matching rows for --diff (default: the first column)",
+    },
+    OptionSpec {
+        name: "--follow",
+        alias: None,
+        value: None,
+        help: "Keep reading new lines and redraw the table as rows arrive",
+    },
+    OptionSpec {
+        name: "--strict",
+        alias: None,
+        value: None,
+        help: "Fail on ragged rows, unknown colors and truncated cells instead of warning",
+    },
+    OptionSpec {
+        name: "--repair",
+        alias: None,
+        value: None,
+        help: "Pad or truncate rows to the number of columns",
+    },
+    OptionSpec {
+        name: "--show-bad-rows",
+        alias: None,
+        value: None,
+        help: "Show only the rows whose field count differs from the column count",
+    },
+    OptionSpec {
+        name: "--drop-empty-rows",
+        alias: None,
+        value: None,
+        help: "Leave out rows whose cells are all empty",
+    },
+    OptionSpec {
+        name: "--drop-empty-cols",
+        alias: None,
+        value: None,
+        help: "Leave out columns that are empty in every shown row",
+    },
+    OptionSpec {
+        name: "--compute",
+        alias: None,
+        value: Some("NAME = EXPR"),
+        help: "Append a column computed from other columns with + - * /; may be repeated",
+    },
+    OptionSpec {
+        name: "--columns",
+        alias: None,
+        value: Some("NAMES"),
+        help: "Show only these columns, in this order",
+    },
+    OptionSpec {
+        name: "--sort-columns",
+        alias: None,
+        value: None,
+        help: "Order columns alphabetically by header",
+    },
+    OptionSpec {
+        name: "--cumulative",
+        alias: None,
+        value: Some("NAME"),
+        help: "Append a column with the running sum of numeric column NAME",
+    },
+    OptionSpec {
+        name: "--cumulative-reset",
+        alias: None,
+        value: None,
+        help: "Restart the --cumulative sum after a cell that is not a number",
+    },
+    OptionSpec {
+        name: "--expand-column",
+        alias: None,
+        value: Some("NAME:SEP"),
+        help: "Show the SEP-separated values of column NAME on separate lines",
+    },
+    OptionSpec {
+        name: "--flatten",
+        alias: None,
+        value: Some("NAME:SEP"),
+        help: "Show the SEP-separated values of column NAME on one line, joined by ', '",
+    },
+    OptionSpec {
+        name: "--ditto",
+        alias: None,
+        value: Some("NAMES"),
+        help: "Replace values repeating the row above in these columns with a ditto mark",
+    },
+    OptionSpec {
+        name: "--ditto-mark",
+        alias: None,
+        value: Some("TEXT"),
+        help: "Mark used by --ditto, or '' to leave the cells blank (default: \")",
+    },
+    OptionSpec {
+        name: "--mask",
+        alias: None,
+        value: Some("NAMES"),
+        help: "Hide the cells of these columns behind a mask",
+    },
+    OptionSpec {
+        name: "--mask-char",
+        alias: None,
+        value: Some("CHAR"),
+        help: "Character used by --mask (default: *)",
+    },
+    OptionSpec {
+        name: "--histogram",
+        alias: None,
+        value: Some("NAME"),
+        help: "Show the distinct values of column NAME and their counts instead of the rows",
+    },
+    OptionSpec {
+        name: "--sample",
+        alias: None,
+        value: Some("N"),
+        help: "Show a uniform random sample of N rows, in their original order",
+    },
+    OptionSpec {
+        name: "--seed",
+        alias: None,
+        value: Some("N"),
+        help: "Seed for --sample, to draw the same sample every time",
+    },
+    OptionSpec {
+        name: "--sort",
+        alias: None,
+        value: Some("NAME"),
+        help: "Sort the rows by a column, numerically when its cells are numbers",
+    },
+    OptionSpec {
+        name: "--group-by",
+        alias: None,
+        value: Some("NAME"),
+        help: "Bring the rows sharing a value in column NAME together",
+    },
+    OptionSpec {
+        name: "--toc",
+        alias: None,
+        value: None,
+        help: "Print an index of the --group-by groups and their row counts first",
+    },
+    OptionSpec {
+        name: "--group-lines",
+        alias: None,
+        value: None,
+        help: "Draw a rule between the groups of --group-by",
+    },
+    OptionSpec {
+        name: "--number",
+        alias: None,
+        value: None,
+        help: "Add a # column numbering the rows",
+    },
+    OptionSpec {
+        name: "--orig-index",
+        alias: None,
+        value: None,
+        help: "Number rows by their position in the input (with --number)",
+    },
+    OptionSpec {
+        name: "--rotate",
+        alias: None,
+        value: None,
+        help: "Rotate the table so that columns become rows",
+    },
+    OptionSpec {
+        name: "--limit",
+        alias: None,
+        value: Some("N"),
+        help: "Show at most N rows, noting how many more were left out",
+    },
+    OptionSpec {
+        name: "--round",
+        alias: None,
+        value: Some("N"),
+        help: "Show decimal numbers with N decimal places",
+    },
+    OptionSpec {
+        name: "--abbrev-numbers",
+        alias: None,
+        value: None,
+        help: "Abbreviate large numbers with K, M, B and T suffixes",
+    },
+    OptionSpec {
+        name: "--bool-symbols",
+        alias: None,
+        value: None,
+        help: "Show the values of boolean columns as \u{2713} and \u{2717}",
+    },
+    OptionSpec {
+        name: "--true-symbol",
+        alias: None,
+        value: Some("TEXT"),
+        help: "Symbol used by --bool-symbols for true values",
+    },
+    OptionSpec {
+        name: "--false-symbol",
+        alias: None,
+        value: Some("TEXT"),
+        help: "Symbol used by --bool-symbols for false values",
+    },
+    OptionSpec {
+        name: "--locale",
+        alias: None,
+        value: Some("TAG"),
+        help: "Write numbers with the separators of a locale, such as de or fr-CA",
+    },
+    OptionSpec {
+        name: "--num-format",
+        alias: None,
+        value: Some("COLUMN:PATTERN,..."),
+        help: "Format numbers of a column with a pattern such as ${:.2}",
+    },
+    OptionSpec {
+        name: "--heatmap",
+        alias: None,
+        value: Some("NAMES"),
+        help: "Color numeric columns on a blue to red gradient from their minimum to maximum",
+    },
+    OptionSpec {
+        name: "--prefix",
+        alias: None,
+        value: Some("COLUMN:TEXT"),
+        help: "Write TEXT before the non-empty cells of a column; may be repeated",
+    },
+    OptionSpec {
+        name: "--suffix",
+        alias: None,
+        value: Some("COLUMN:TEXT"),
+        help: "Write TEXT after the non-empty cells of a column; may be repeated",
+    },
+    OptionSpec {
+        name: "--schema",
+        alias: None,
+        value: None,
+        help: "Print each column's name and inferred type instead of the data",
+    },
+    OptionSpec {
+        name: "--format",
+        alias: None,
+        value: Some("table|plain|aligned-tsv|latex|jira|json"),
+        help: "Output format",
+    },
+    OptionSpec {
+        name: "--json-typed",
+        alias: None,
+        value: None,
+        help: "Write numbers and booleans in JSON output unquoted",
+    },
+    OptionSpec {
+        name: "--border",
+        alias: None,
+        value: Some("ascii|unicode|rounded|heavy"),
+        help: "Border style of the table",
+    },
+    OptionSpec {
+        name: "--force-unicode",
+        alias: None,
+        value: None,
+        help: "Use Unicode borders even when the locale is not UTF-8",
+    },
+    OptionSpec {
+        name: "--row-header",
+        alias: None,
+        value: None,
+        help: "Show the first column in bold with a heavier divider as row labels",
+    },
+    OptionSpec {
+        name: "--outer-only",
+        alias: None,
+        value: None,
+        help: "Draw only the outer border",
+    },
+    OptionSpec {
+        name: "--hide-header",
+        alias: None,
+        value: Some("NAMES"),
+        help: "Leave the header cells of these columns blank",
+    },
+    OptionSpec {
+        name: "--no-header-rule",
+        alias: None,
+        value: None,
+        help: "Keep the header but omit the rule below it",
+    },
+    OptionSpec {
+        name: "--align",
+        alias: None,
+        value: Some("l|r|c|auto"),
+        help: "Alignment of columns without an inline alignment (default: auto)",
+    },
+    OptionSpec {
+        name: "--auto-align",
+        alias: None,
+        value: None,
+        help: "Align numbers and booleans by type even when --align is given",
+    },
+    OptionSpec {
+        name: "--text-columns",
+        alias: None,
+        value: Some("NAMES"),
+        help: "Treat these columns as text so they are never aligned as numbers",
+    },
+    OptionSpec {
+        name: "--width",
+        alias: None,
+        value: Some("N"),
+        help: "Total table width used for percentage widths (default: $COLUMNS or 80)",
+    },
+    OptionSpec {
+        name: "--col-widths",
+        alias: None,
+        value: Some("W1,W2,..."),
+        help: "Fixed column widths, as absolute widths or percentages of --width",
+    },
+    OptionSpec {
+        name: "--max-col-widths",
+        alias: None,
+        value: Some("W1,W2,..."),
+        help: "Maximum column widths; empty entries leave a column uncapped",
+    },
+    OptionSpec {
+        name: "--pad-to",
+        alias: None,
+        value: Some("N"),
+        help: "Widen the columns of a narrower table so it is N columns wide",
+    },
+    OptionSpec {
+        name: "--truncate-middle",
+        alias: None,
+        value: None,
+        help: "Cut the middle out of cells that do not fit instead of their end",
+    },
+    OptionSpec {
+        name: "--truncate-ratio",
+        alias: None,
+        value: Some("R"),
+        help:
+            "Share of the width kept for the start of cells cut by --truncate-middle (default 0.5)",
+    },
+    OptionSpec {
+        name: "--wrap-headers",
+        alias: None,
+        value: Some("N"),
+        help: "Wrap header text at N columns without wrapping the cells",
+    },
+    OptionSpec {
+        name: "--page-columns",
+        alias: None,
+        value: None,
+        help: "Split a table wider than --width into pages of columns",
+    },
+    OptionSpec {
+        name: "--freeze",
+        alias: None,
+        value: Some("N"),
+        help: "Repeat the first N columns on every page of columns",
+    },
+    OptionSpec {
+        name: "--jobs",
+        alias: None,
+        value: Some("N"),
+        help: "Measure column widths on N threads",
+    },
+    OptionSpec {
+        name: "--split-by",
+        alias: None,
+        value: Some("COLUMN"),
+        help: "Write one table per value of COLUMN into --out-dir",
+    },
+    OptionSpec {
+        name: "--out-dir",
+        alias: None,
+        value: Some("DIR"),
+        help: "The directory --split-by writes its tables to",
+    },
+    OptionSpec {
+        name: "--meta-out",
+        alias: None,
+        value: Some("FILE"),
+        help:
+            "Also write the name, type, width, alignment and range of each column to FILE as JSON",
+    },
+    OptionSpec {
+        name: "--no-trailing-newline",
+        alias: None,
+        value: None,
+        help: "Leave out the newline after the last line of the table",
+    },
+    OptionSpec {
+        name: "--clipboard",
+        alias: None,
+        value: None,
+        help: "Copy the uncolored table to the clipboard instead of printing it",
+    },
+    OptionSpec {
+        name: "--hyperlinks",
+        alias: None,
+        value: None,
+        help: "Show cells holding a URL as clickable terminal hyperlinks",
+    },
+    OptionSpec {
+        name: "--header-color",
+        alias: None,
+        value: Some("COLOR"),
+        help: "Color of the header text",
+    },
+    OptionSpec {
+        name: "--cell-color",
+        alias: None,
+        value: Some("COLOR"),
+        help: "Color of the cell text",
+    },
+    OptionSpec {
+        name: "--stripe",
+        alias: None,
+        value: Some("COLOR"),
+        help: "Color of every second row",
+    },
+    OptionSpec {
+        name: "--alt-column-color",
+        alias: None,
+        value: Some("COLOR"),
+        help: "Color of every second column",
+    },
+    OptionSpec {
+        name: "--theme",
+        alias: None,
+        value: Some("minimal|heavy|colorful"),
+        help: "Preset of border style, header color and stripe color",
+    },
+    OptionSpec {
+        name: "--color-scale",
+        alias: None,
+        value: Some("COLUMN:COLOR<N,...,COLOR"),
+        help: "Color a numeric column by threshold; may be repeated",
+    },
+    OptionSpec {
+        name: "--value-color",
+        alias: None,
+        value: Some("COLUMN:VALUE=COLOR,..."),
+        help: "Color exact values of a column; may be repeated",
+    },
+    OptionSpec {
+        name: "--empty-message",
+        alias: None,
+        value: Some("TEXT"),
+        help: "Message shown inside the table when there are no rows",
+    },
+    OptionSpec {
+        name: "--banner",
+        alias: None,
+        value: Some("TEXT"),
+        help: "Show TEXT centered in a row across every column above the header",
+    },
+    OptionSpec {
+        name: "--caption",
+        alias: None,
+        value: Some("TEXT"),
+        help: "Print a caption below the table",
+    },
+    OptionSpec {
+        name: "--caption-align",
+        alias: None,
+        value: Some("l|r|c"),
+        help: "Alignment of the caption (default: l)",
+    },
+    OptionSpec {
+        name: "--dim",
+        alias: None,
+        value: Some("PREDICATE"),
+        help: "Dim the rows matching PREDICATE, such as 'status==inactive'",
+    },
+    OptionSpec {
+        name: "--legend",
+        alias: None,
+        value: None,
+        help: "Print a key explaining the active color rules",
+    },
+    OptionSpec {
+        name: "--marks",
+        alias: None,
+        value: None,
+        help: "Mark cells colored by a rule with (!), (~), (+) or (*) instead of the color",
+    },
+    OptionSpec {
+        name: "--quiet",
+        alias: Some("-q"),
+        value: None,
+        help: "Only print the table and errors, without progress or warnings",
+    },
+    OptionSpec {
+        name: "--verbose",
+        alias: Some("-v"),
+        value: None,
+        help: "Print notes on stderr explaining how the table was built",
+    },
+    OptionSpec {
+        name: "--profile",
+        alias: None,
+        value: Some("FILE"),
+        help: "Read option defaults from a TOML profile; given options take precedence",
+    },
+    OptionSpec {
+        name: "-h",
+        alias: Some("--help"),
+        value: None,
+        help: "Print this help and exit",
+    },
+    OptionSpec {
+        name: "-V",
+        alias: Some("--version"),
+        value: None,
+        help: "Print the version and exit",
+    },
+];
+
+/// An option's name and, for options that take one, its value.
+pub type ParsedOption = (&'static str, Option<String>);
+
+/// The options given on the command line, in the order they appeared.
+#[derive(Debug, Default)]
+pub struct Args {
+    options: Vec<ParsedOption>,
+}
+
+impl Args {
+    /// Parse `args`, excluding the program name, against `OPTIONS`.
+    ///
+    /// Values may follow their option as the next argument or after `=`.
+    ///
+    pub fn parse(args: &[String]) -> Result<Args, String> {
+        let mut parsed = Args::default();
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            let (flag, inline) = match arg.split_once('=') {
+                Some((flag, value)) if flag.starts_with("--") => (flag, Some(value.to_string())),
+                _ => (arg.as_str(), None),
+            };
+            let spec = OPTIONS
+                .iter()
+                .find(|spec| spec.name == flag || spec.alias == Some(flag))
+                .ok_or_else(|| format!("Unknown option: {}", arg))?;
+            let value = match (spec.value, inline) {
+                (None, None) => None,
+                (None, Some(_)) => return Err(format!("{} does not take a value", flag)),
+                (Some(_), Some(value)) => Some(value),
+                (Some(_), None) => Some(
+                    args.next()
+                        .ok_or_else(|| format!("{} requires a value", flag))?
+                        .clone(),
+                ),
+            };
+            parsed.options.push((spec.name, value));
+        }
+        Ok(parsed)
+    }
+
+    /// Return the value of the last occurrence of option `name`.
+    pub fn value(&self, name: &str) -> Option<&str> {
+        self.values(name).pop()
+    }
+
+    /// Return the values of every occurrence of option `name`.
+    pub fn values(&self, name: &str) -> Vec<&str> {
+        self.options
+            .iter()
+            .filter(|(option, _)| *option == name)
+            .filter_map(|(_, value)| value.as_deref())
+            .collect()
+    }
+
+    /// Return whether option `name` was given.
+    pub fn flag(&self, name: &str) -> bool {
+        self.options.iter().any(|(option, _)| *option == name)
+    }
+
+    /// Add the `defaults` whose option was not given, such as those read from a profile.
+    pub fn with_defaults(mut self, defaults: Vec<ParsedOption>) -> Args {
+        let defaults: Vec<_> = defaults
+            .into_iter()
+            .filter(|(name, _)| !self.flag(name))
+            .collect();
+        self.options.splice(0..0, defaults);
+        self
+    }
+}
+
+/// The one-line usage summary printed on argument errors.
+pub const USAGE: &str = "Usage: tabb -c \"column1,column2,...\" [OPTIONS]";
+
+/// Write the usage summary and a description of every option.
+pub fn write_help(writer: &mut impl Write) -> io::Result<()> {
+    writeln!(writer, "tabbs {}", env!("CARGO_PKG_VERSION"))?;
+    writeln!(writer, "Print delimited data from stdin as a table.")?;
+    writeln!(writer)?;
+    writeln!(writer, "{}", USAGE)?;
+    writeln!(writer)?;
+    writeln!(writer, "Options:")?;
+    let labels: Vec<String> = OPTIONS
+        .iter()
+        .map(|spec| {
+            let names = match spec.alias {
+                Some(alias) if !alias.starts_with("--") => format!("{}, {}", alias, spec.name),
+                Some(alias) => format!("{}, {}", spec.name, alias),
+                None => spec.name.to_string(),
+            };
+            match spec.value {
+                Some(value) => format!("{} {}", names, value),
+                None => names,
+            }
+        })
+        .collect();
+    let width = labels.iter().map(|label| label.len()).max().unwrap_or(0);
+    for (label, spec) in labels.iter().zip(OPTIONS) {
+        writeln!(writer, "  {:<width$}  {}", label, spec.help, width = width)?;
+    }
+    Ok(())
+}
+
+/// Write the program name and crate version.
+pub fn write_version(writer: &mut impl Write) -> io::Result<()> {
+    writeln!(writer, "tabbs {}", env!("CARGO_PKG_VERSION"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Result<Args, String> {
+        Args::parse(&args.iter().map(|s| s.to_string()).collect::<Vec<_>>())
+    }
+
+    #[test]
+    fn test_parse_args() {
+        let parsed = args(&["-c", "name,age", "-d", ";", "--width=40", "--repair"]).unwrap();
+        assert_eq!(parsed.value("-c"), Some("name,age"));
+        assert_eq!(parsed.value("--delimiter"), Some(";"));
+        assert_eq!(parsed.value("--width"), Some("40"));
+        assert!(parsed.flag("--repair"));
+        assert!(!parsed.flag("--legend"));
+
+        assert!(args(&["--bogus"]).is_err());
+        assert!(args(&["-c"]).is_err());
+        assert!(args(&["--repair=yes"]).is_err());
+    }
+
+    #[test]
+    fn test_version_and_help() {
+        let mut output = Vec::new();
+        write_version(&mut output).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            format!("tabbs {}\n", env!("CARGO_PKG_VERSION"))
+        );
+
+        let mut output = Vec::new();
+        write_help(&mut output).unwrap();
+        let help = String::from_utf8(output).unwrap();
+        for spec in OPTIONS {
+            assert!(help.contains(spec.name) && help.contains(spec.help));
+        }
+    }
+}
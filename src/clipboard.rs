@@ -0,0 +1,67 @@
+//! Copying rendered tables to the system clipboard with `--clipboard`.
+
+use std::io::{self, Write};
+use std::process::{Command, Stdio};
+
+/// Clipboard programs tried in order, with the arguments that make them read stdin.
+const COMMANDS: &[&[&str]] = &[
+    &["pbcopy"],
+    &["wl-copy"],
+    &["xclip", "-selection", "clipboard"],
+    &["xsel", "--clipboard", "--input"],
+    &["clip"],
+];
+
+/// Copy `text` to the system clipboard using the first available clipboard program.
+pub fn copy(text: &str) -> io::Result<()> {
+    for command in COMMANDS {
+        match copy_with(command, text) {
+            Err(err) if err.kind() == io::ErrorKind::NotFound => continue,
+            result => return result,
+        }
+    }
+    Err(io::Error::new(
+        io::ErrorKind::NotFound,
+        "no clipboard program found (tried pbcopy, wl-copy, xclip, xsel and clip)",
+    ))
+}
+
+/// Pipe `text` to the standard input of `command`, failing if it exits unsuccessfully.
+pub fn copy_with(command: &[&str], text: &str) -> io::Result<()> {
+    let (program, args) = command
+        .split_first()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "empty clipboard command"))?;
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .spawn()?;
+    child.stdin.take().unwrap().write_all(text.as_bytes())?;
+    let status = child.wait()?;
+    if !status.success() {
+        return Err(io::Error::other(format!(
+            "{} exited with {}",
+            program, status
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_copy_with() {
+        let path = std::env::temp_dir().join(format!("tabbs-clipboard-{}", std::process::id()));
+        let script = format!("cat > '{}'", path.display());
+        copy_with(&["sh", "-c", &script], "+---+\n| a |\n+---+\n").unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "+---+\n| a |\n+---+\n"
+        );
+        std::fs::remove_file(path).unwrap();
+
+        assert!(copy_with(&["sh", "-c", "exit 1"], "").is_err());
+    }
+}
@@ -0,0 +1,323 @@
+//! Conditional coloring rules applied to individual cells.
+
+use crate::error::TabbsError;
+use colored::{Color, Colorize};
+
+/// Check a color given on the command line, returning it unchanged when valid.
+///
+/// A bare integer is an index into the 256-color xterm palette and must be at most
+/// 255, and `#rrggbb` is a truecolor value. Other values are color names.
+///
+pub fn parse_color(value: &str) -> Result<String, TabbsError> {
+    if value.starts_with('#') && rgb(value).is_none() {
+        return Err(TabbsError::Config(format!("Invalid color: {}", value)));
+    }
+    if !value.is_empty()
+        && value.chars().all(|c| c.is_ascii_digit())
+        && palette_index(value).is_none()
+    {
+        return Err(TabbsError::Config(format!(
+            "Invalid color index: {}",
+            value
+        )));
+    }
+    Ok(value.to_string())
+}
+
+/// Return the 256-color palette index named by `color`, if it is a bare integer.
+fn palette_index(color: &str) -> Option<u8> {
+    if color.chars().all(|c| c.is_ascii_digit()) {
+        color.parse().ok()
+    } else {
+        None
+    }
+}
+
+/// Whether `color` is a color name, palette index or truecolor value that `paint` knows.
+pub fn is_known_color(color: &str) -> bool {
+    palette_index(color).is_some() || rgb(color).is_some() || color.parse::<Color>().is_ok()
+}
+
+/// Return the red, green and blue components of a `#rrggbb` color.
+fn rgb(color: &str) -> Option<(u8, u8, u8)> {
+    let hex = color.strip_prefix('#').filter(|hex| hex.len() == 6)?;
+    let component = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+    Some((component(0)?, component(2)?, component(4)?))
+}
+
+/// Return the `#rrggbb` color of `value` on a blue to red gradient from `min` to `max`.
+///
+/// Values outside the range get the color of the nearest end, and every value
+/// is blue when `min` and `max` are equal.
+///
+pub fn gradient_color(value: f64, min: f64, max: f64) -> String {
+    let position = if max > min {
+        ((value - min) / (max - min)).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+    let red = (255.0 * position).round() as u8;
+    format!("#{:02x}00{:02x}", red, 255 - red)
+}
+
+/// Return `text` styled with `color` and optionally bold, when colors are enabled.
+///
+/// `color` is a color name, a 256-color palette index such as `208` or a
+/// truecolor value such as `#ff8800`.
+///
+pub fn paint(text: &str, color: Option<&str>, bold: bool) -> String {
+    if let Some((red, green, blue)) = color.and_then(rgb) {
+        if !colored::control::SHOULD_COLORIZE.should_colorize() {
+            return text.to_string();
+        }
+        let bold = if bold { "1;" } else { "" };
+        return format!(
+            "\x1b[{}38;2;{};{};{}m{}\x1b[0m",
+            bold, red, green, blue, text
+        );
+    }
+    if let Some(index) = color.and_then(palette_index) {
+        if !colored::control::SHOULD_COLORIZE.should_colorize() {
+            return text.to_string();
+        }
+        let bold = if bold { "1;" } else { "" };
+        return format!("\x1b[{}38;5;{}m{}\x1b[0m", bold, index, text);
+    }
+    let mut styled = text.normal();
+    if let Some(color) = color {
+        styled = styled.color(color);
+    }
+    if bold {
+        styled = styled.bold();
+    }
+    styled.to_string()
+}
+
+/// Return the textual marker standing in for `color` when colors are replaced by marks.
+///
+/// Red marks errors with `(!)`, yellow warnings with `(~)` and green good values
+/// with `(+)`; every other color is a highlight, `(*)`.
+///
+pub fn color_mark(color: &str) -> &'static str {
+    let color = color.to_lowercase();
+    match color
+        .trim_start_matches("bright")
+        .trim_start_matches([' ', '_'])
+    {
+        "red" => "(!)",
+        "yellow" => "(~)",
+        "green" => "(+)",
+        _ => "(*)",
+    }
+}
+
+/// A rule coloring the numeric cells of a column by threshold buckets.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColorScale {
+    /// The name of the column the rule applies to.
+    pub column: String,
+    /// Upper bounds and their colors, sorted ascending by bound.
+    pub thresholds: Vec<(f64, String)>,
+    /// The color of values at or above the highest bound.
+    pub default: String,
+}
+
+impl ColorScale {
+    /// Return the color for `cell`, or `None` when it is not numeric.
+    pub fn color_for(&self, cell: &str) -> Option<&str> {
+        let value: f64 = cell.trim().parse().ok()?;
+        self.thresholds
+            .iter()
+            .find(|(bound, _)| value < *bound)
+            .map_or(Some(self.default.as_str()), |(_, color)| Some(color))
+    }
+
+    /// Return each color of the scale paired with the condition it represents.
+    pub fn legend_entries(&self) -> Vec<(&str, String)> {
+        let mut entries = Vec::new();
+        let mut lower: Option<f64> = None;
+        for (bound, color) in &self.thresholds {
+            let condition = match lower {
+                Some(lower) => format!("{} <= {} < {}", lower, self.column, bound),
+                None => format!("{} < {}", self.column, bound),
+            };
+            entries.push((color.as_str(), condition));
+            lower = Some(*bound);
+        }
+        let condition = match lower {
+            Some(lower) => format!("{} >= {}", self.column, lower),
+            None => format!("any {}", self.column),
+        };
+        entries.push((self.default.as_str(), condition));
+        entries
+    }
+}
+
+/// Parse a color scale such as `cpu:green<50,yellow<80,red`.
+///
+/// Each `COLOR<N` entry colors values below `N`; the final bare color applies to
+/// everything above the highest threshold.
+///
+pub fn parse_color_scale(spec: &str) -> Result<ColorScale, TabbsError> {
+    let invalid = || TabbsError::Config(format!("Invalid color scale: {}", spec));
+    let (column, rules) = spec.split_once(':').ok_or_else(invalid)?;
+    let mut thresholds = Vec::new();
+    let mut default = None;
+    for rule in rules.split(',') {
+        if default.is_some() {
+            return Err(invalid());
+        }
+        match rule.split_once('<') {
+            Some((color, bound)) => {
+                let bound: f64 = bound.trim().parse().map_err(|_| invalid())?;
+                thresholds.push((bound, color.trim().to_string()));
+            }
+            None => default = Some(rule.trim().to_string()),
+        }
+    }
+    let default = default
+        .filter(|color| !color.is_empty())
+        .ok_or_else(invalid)?;
+    thresholds.sort_by(|a, b| a.0.total_cmp(&b.0));
+    Ok(ColorScale {
+        column: column.trim().to_string(),
+        thresholds,
+        default,
+    })
+}
+
+/// A rule coloring exact cell values of a column.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValueColors {
+    /// The name of the column the rule applies to.
+    pub column: String,
+    /// Cell values and their colors.
+    pub colors: Vec<(String, String)>,
+}
+
+impl ValueColors {
+    /// Return the color mapped to `cell`, if any.
+    pub fn color_for(&self, cell: &str) -> Option<&str> {
+        self.colors
+            .iter()
+            .find(|(value, _)| value == cell)
+            .map(|(_, color)| color.as_str())
+    }
+
+    /// Return each color of the mapping paired with the condition it represents.
+    pub fn legend_entries(&self) -> Vec<(&str, String)> {
+        self.colors
+            .iter()
+            .map(|(value, color)| (color.as_str(), format!("{} is {}", self.column, value)))
+            .collect()
+    }
+}
+
+/// Parse a value color mapping such as `status:OK=green,FAIL=red`.
+pub fn parse_value_colors(spec: &str) -> Result<ValueColors, TabbsError> {
+    let invalid = || TabbsError::Config(format!("Invalid value colors: {}", spec));
+    let (column, mappings) = spec.split_once(':').ok_or_else(invalid)?;
+    let colors = mappings
+        .split(',')
+        .map(|mapping| {
+            let (value, color) = mapping.rsplit_once('=').ok_or_else(invalid)?;
+            match color.trim() {
+                "" => Err(invalid()),
+                color => Ok((value.to_string(), color.to_string())),
+            }
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(ValueColors {
+        column: column.trim().to_string(),
+        colors,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_color_scale_thresholds() {
+        let scale = parse_color_scale("cpu:yellow<80,green<50,red").unwrap();
+        assert_eq!(
+            scale.thresholds,
+            vec![(50.0, "green".to_string()), (80.0, "yellow".to_string())]
+        );
+        assert_eq!(scale.color_for("30"), Some("green"));
+        assert_eq!(scale.color_for("70"), Some("yellow"));
+        assert_eq!(scale.color_for("90"), Some("red"));
+        assert_eq!(scale.color_for("n/a"), None);
+        assert!(parse_color_scale("cpu:green<50").is_err());
+        assert!(parse_color_scale("cpu:green<high,red").is_err());
+    }
+
+    #[test]
+    fn test_legend_entries() {
+        let scale = parse_color_scale("cpu:green<50,yellow<80,red").unwrap();
+        assert_eq!(
+            scale.legend_entries(),
+            vec![
+                ("green", "cpu < 50".to_string()),
+                ("yellow", "50 <= cpu < 80".to_string()),
+                ("red", "cpu >= 80".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_value_colors() {
+        let rule = parse_value_colors("status:OK=green,FAIL=red").unwrap();
+        assert_eq!(rule.column, "status");
+        assert_eq!(rule.color_for("OK"), Some("green"));
+        assert_eq!(rule.color_for("FAIL"), Some("red"));
+        assert_eq!(rule.color_for("ok"), None);
+        assert!(parse_value_colors("status:OK").is_err());
+        assert!(parse_value_colors("status:OK=").is_err());
+    }
+
+    #[test]
+    fn test_palette_colors() {
+        colored::control::set_override(true);
+        assert_eq!(paint("hi", Some("208"), false), "\x1b[38;5;208mhi\x1b[0m");
+        assert_eq!(paint("hi", Some("208"), true), "\x1b[1;38;5;208mhi\x1b[0m");
+        assert_eq!(paint("hi", Some("red"), false), "\x1b[31mhi\x1b[0m");
+        assert_eq!(parse_color("208").unwrap(), "208");
+        assert!(parse_color("256").is_err());
+        assert_eq!(parse_color("blue").unwrap(), "blue");
+    }
+
+    #[test]
+    fn test_color_mark() {
+        assert_eq!(color_mark("red"), "(!)");
+        assert_eq!(color_mark("Bright Red"), "(!)");
+        assert_eq!(color_mark("yellow"), "(~)");
+        assert_eq!(color_mark("green"), "(+)");
+        assert_eq!(color_mark("blue"), "(*)");
+        assert_eq!(color_mark("208"), "(*)");
+    }
+
+    #[test]
+    fn test_truecolor_gradient() {
+        colored::control::set_override(true);
+        assert_eq!(gradient_color(10.0, 10.0, 30.0), "#0000ff");
+        assert_eq!(gradient_color(20.0, 10.0, 30.0), "#80007f");
+        assert_eq!(gradient_color(30.0, 10.0, 30.0), "#ff0000");
+        assert_eq!(gradient_color(5.0, 5.0, 5.0), "#0000ff");
+        assert_eq!(
+            paint("hi", Some("#ff8800"), false),
+            "\x1b[38;2;255;136;0mhi\x1b[0m"
+        );
+        assert!(parse_color("#ff8800").is_ok());
+        assert!(parse_color("#ff88").is_err());
+    }
+
+    #[test]
+    fn test_is_known_color() {
+        assert!(is_known_color("red"));
+        assert!(is_known_color("bright blue"));
+        assert!(is_known_color("208"));
+        assert!(is_known_color("#ff8800"));
+        assert!(!is_known_color("reddish"));
+    }
+}
@@ -0,0 +1,171 @@
+//! Comparison of two tables with the same columns, matched by a key column.
+
+use crate::error::TabbsError;
+use std::collections::HashMap;
+
+/// How a row of the compared table differs from the base table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RowStatus {
+    /// The key only appears in the compared table.
+    Added,
+    /// The key only appears in the base table.
+    Removed,
+    /// The key appears in both tables with different cells.
+    Changed,
+    /// The key appears in both tables with the same cells.
+    Unchanged,
+}
+
+impl RowStatus {
+    /// The marker shown in the status column.
+    pub fn marker(self) -> &'static str {
+        match self {
+            RowStatus::Added => "+",
+            RowStatus::Removed => "-",
+            RowStatus::Changed => "~",
+            RowStatus::Unchanged => "",
+        }
+    }
+
+    /// The color used for rows with this status, if any.
+    pub fn color(self) -> Option<&'static str> {
+        match self {
+            RowStatus::Added => Some("green"),
+            RowStatus::Removed => Some("red"),
+            RowStatus::Changed => Some("yellow"),
+            RowStatus::Unchanged => None,
+        }
+    }
+}
+
+/// A row of the comparison of two tables.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiffRow {
+    /// How the row differs from the base table.
+    pub status: RowStatus,
+    /// The cells of the row, from the compared table unless it was removed.
+    pub cells: Vec<String>,
+    /// Whether each cell differs from the base table, which is only ever the case
+    /// for changed rows.
+    pub changed: Vec<bool>,
+}
+
+/// Compare `new` against `old`, matching rows by the cell in column `key`.
+///
+/// Rows are returned in the order of `old`, followed by the rows only found in
+/// `new`. Changed rows hold the new cells and mark the ones that differ. Returns
+/// an error if a key appears more than once in either table.
+///
+pub fn diff_rows(
+    old: &[Vec<String>],
+    new: &[Vec<String>],
+    key: usize,
+) -> Result<Vec<DiffRow>, TabbsError> {
+    let old_by_key = rows_by_key(old, key)?;
+    let new_by_key = rows_by_key(new, key)?;
+    let unmarked = |row: &Vec<String>| vec![false; row.len()];
+
+    let mut diff = Vec::new();
+    for old_row in old {
+        let Some(new_row) = new_by_key.get(key_of(old_row, key)) else {
+            diff.push(DiffRow {
+                status: RowStatus::Removed,
+                cells: old_row.clone(),
+                changed: unmarked(old_row),
+            });
+            continue;
+        };
+        let width = old_row.len().max(new_row.len());
+        let cell = |row: &Vec<String>, i: usize| row.get(i).cloned().unwrap_or_default();
+        let cells: Vec<String> = (0..width).map(|i| cell(new_row, i)).collect();
+        let changed: Vec<bool> = (0..width).map(|i| cell(old_row, i) != cells[i]).collect();
+        let status = if changed.contains(&true) {
+            RowStatus::Changed
+        } else {
+            RowStatus::Unchanged
+        };
+        diff.push(DiffRow {
+            status,
+            cells,
+            changed,
+        });
+    }
+    for new_row in new {
+        if !old_by_key.contains_key(key_of(new_row, key)) {
+            diff.push(DiffRow {
+                status: RowStatus::Added,
+                cells: new_row.clone(),
+                changed: unmarked(new_row),
+            });
+        }
+    }
+    Ok(diff)
+}
+
+/// Index `rows` by their cell in column `key`, rejecting a key that appears twice.
+fn rows_by_key(
+    rows: &[Vec<String>],
+    key: usize,
+) -> Result<HashMap<&str, &Vec<String>>, TabbsError> {
+    let mut by_key = HashMap::new();
+    for row in rows {
+        let value = key_of(row, key);
+        if by_key.insert(value, row).is_some() {
+            return Err(TabbsError::Config(format!("Duplicate key: {}", value)));
+        }
+    }
+    Ok(by_key)
+}
+
+/// Return the cell of `row` in column `key`, or an empty key if the row is short.
+fn key_of(row: &[String], key: usize) -> &str {
+    row.get(key).map_or("", String::as_str)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rows(rows: &[&[&str]]) -> Vec<Vec<String>> {
+        rows.iter()
+            .map(|row| row.iter().map(|s| s.to_string()).collect())
+            .collect()
+    }
+
+    #[test]
+    fn test_diff_rows() {
+        let old = rows(&[
+            &["1", "jack", "35"],
+            &["2", "jane", "50"],
+            &["3", "erin", "20"],
+        ]);
+        let new = rows(&[
+            &["3", "erin", "20"],
+            &["2", "jane", "51"],
+            &["4", "finn", "41"],
+        ]);
+        let diff = diff_rows(&old, &new, 0).unwrap();
+        let statuses: Vec<RowStatus> = diff.iter().map(|row| row.status).collect();
+        assert_eq!(
+            statuses,
+            vec![
+                RowStatus::Removed,
+                RowStatus::Changed,
+                RowStatus::Unchanged,
+                RowStatus::Added,
+            ]
+        );
+        assert_eq!(diff[0].cells, rows(&[&["1", "jack", "35"]])[0]);
+        assert_eq!(diff[1].cells, rows(&[&["2", "jane", "51"]])[0]);
+        assert_eq!(diff[1].changed, vec![false, false, true]);
+        assert_eq!(diff[2].changed, vec![false, false, false]);
+        assert_eq!(diff[3].cells, rows(&[&["4", "finn", "41"]])[0]);
+
+        let duplicated = rows(&[&["2", "jane", "51"], &["2", "jane", "52"]]);
+        assert!(matches!(
+            diff_rows(&old, &duplicated, 0),
+            Err(TabbsError::Config(message)) if message == "Duplicate key: 2"
+        ));
+        assert!(diff_rows(&duplicated, &new, 0).is_err());
+    }
+}
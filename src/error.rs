@@ -0,0 +1,64 @@
+//! The error type returned by the tabbs library.
+
+use std::fmt;
+use std::io;
+
+/// An error from reading, parsing or rendering a table.
+#[derive(Debug)]
+pub enum TabbsError {
+    /// Reading input or writing output failed.
+    Io(io::Error),
+    /// The input could not be parsed.
+    Parse {
+        /// The 1-based line of the input where the problem was found.
+        line: usize,
+        /// A description of the problem.
+        message: String,
+    },
+    /// An option or specification was invalid.
+    Config(String),
+}
+
+impl fmt::Display for TabbsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TabbsError::Io(err) => write!(f, "{}", err),
+            TabbsError::Parse { line, message } => write!(f, "line {}: {}", line, message),
+            TabbsError::Config(message) => f.write_str(message),
+        }
+    }
+}
+
+impl std::error::Error for TabbsError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            TabbsError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for TabbsError {
+    fn from(err: io::Error) -> Self {
+        TabbsError::Io(err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_variants() {
+        let err = crate::parse_kv_input("name: jack\nage 35\n".as_bytes()).unwrap_err();
+        assert!(matches!(err, TabbsError::Parse { line: 2, .. }));
+        assert_eq!(err.to_string(), "line 2: expected `key: value`");
+
+        let err = crate::parse_color_scale("cpu").unwrap_err();
+        assert!(matches!(err, TabbsError::Config(_)));
+        assert_eq!(err.to_string(), "Invalid color scale: cpu");
+
+        let err = crate::parse_input(&[0xff, b'\n'][..], ',', &Default::default()).unwrap_err();
+        assert!(matches!(err, TabbsError::Io(_)));
+    }
+}
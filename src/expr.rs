@@ -0,0 +1,306 @@
+//! A small arithmetic expression language for `--compute`, and row predicates
+//! such as `status==inactive`.
+
+use crate::error::TabbsError;
+
+/// An arithmetic expression over the numeric cells of a row.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    /// A numeric literal.
+    Number(f64),
+    /// The value of the named column.
+    Column(String),
+    /// The negation of an expression.
+    Neg(Box<Expr>),
+    /// An operator (`+`, `-`, `*` or `/`) applied to two expressions.
+    Binary(char, Box<Expr>, Box<Expr>),
+}
+
+impl Expr {
+    /// Evaluate the expression, looking column values up with `column`.
+    ///
+    /// Returns `None` if a column is not a number or a division is by zero.
+    ///
+    pub fn eval(&self, column: &impl Fn(&str) -> Option<f64>) -> Option<f64> {
+        match self {
+            Expr::Number(value) => Some(*value),
+            Expr::Column(name) => column(name),
+            Expr::Neg(expr) => expr.eval(column).map(|value| -value),
+            Expr::Binary(op, left, right) => {
+                let (left, right) = (left.eval(column)?, right.eval(column)?);
+                match op {
+                    '+' => Some(left + right),
+                    '-' => Some(left - right),
+                    '*' => Some(left * right),
+                    _ if right == 0.0 => None,
+                    _ => Some(left / right),
+                }
+            }
+        }
+    }
+
+    /// Return the names of every column the expression refers to.
+    pub fn columns(&self) -> Vec<&str> {
+        match self {
+            Expr::Number(_) => Vec::new(),
+            Expr::Column(name) => vec![name.as_str()],
+            Expr::Neg(expr) => expr.columns(),
+            Expr::Binary(_, left, right) => {
+                let mut columns = left.columns();
+                columns.extend(right.columns());
+                columns
+            }
+        }
+    }
+}
+
+/// The comparison operators of a `Predicate`, longest first so that `<=` is found before `<`.
+const COMPARISONS: [&str; 6] = ["==", "!=", "<=", ">=", "<", ">"];
+
+/// A comparison of the cells of one column against a value.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Predicate {
+    /// The name of the column compared.
+    pub column: String,
+    /// One of `==`, `!=`, `<`, `<=`, `>` or `>=`.
+    pub op: &'static str,
+    /// The value the cells are compared against.
+    pub value: String,
+}
+
+impl Predicate {
+    /// Return whether `cell` satisfies the comparison.
+    ///
+    /// Cells and values that are both numbers are compared as numbers. Otherwise
+    /// `==` and `!=` compare the trimmed text, and the ordering operators are false.
+    ///
+    pub fn matches(&self, cell: &str) -> bool {
+        let cell = cell.trim();
+        if let (Ok(cell), Ok(value)) = (cell.parse::<f64>(), self.value.parse::<f64>()) {
+            return match self.op {
+                "==" => cell == value,
+                "!=" => cell != value,
+                "<" => cell < value,
+                "<=" => cell <= value,
+                ">" => cell > value,
+                _ => cell >= value,
+            };
+        }
+        match self.op {
+            "==" => cell == self.value,
+            "!=" => cell != self.value,
+            _ => false,
+        }
+    }
+}
+
+/// Parse a predicate such as `status==inactive` or `cpu >= 80`.
+pub fn parse_predicate(spec: &str) -> Result<Predicate, TabbsError> {
+    let invalid = || TabbsError::Config(format!("Invalid predicate: {}", spec));
+    let start = spec.find(['=', '!', '<', '>']).ok_or_else(invalid)?;
+    let op = COMPARISONS
+        .into_iter()
+        .find(|op| spec[start..].starts_with(op))
+        .ok_or_else(invalid)?;
+    let column = spec[..start].trim();
+    if column.is_empty() {
+        return Err(invalid());
+    }
+    Ok(Predicate {
+        column: column.to_string(),
+        op,
+        value: spec[start + op.len()..].trim().to_string(),
+    })
+}
+
+/// Parse a definition such as `total = price * qty` into the new column's name
+/// and its expression.
+pub fn parse_compute(spec: &str) -> Result<(String, Expr), TabbsError> {
+    let invalid = || TabbsError::Config(format!("Invalid computed column: {}", spec));
+    let (name, expr) = spec.split_once('=').ok_or_else(invalid)?;
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(invalid());
+    }
+    Ok((name.to_string(), parse_expr(expr)?))
+}
+
+/// Parse an expression of numbers, column names, `+ - * /` and parentheses.
+pub fn parse_expr(text: &str) -> Result<Expr, TabbsError> {
+    let mut parser = Parser {
+        tokens: tokenize(text).map_err(TabbsError::Config)?,
+        position: 0,
+    };
+    let expr = parser.sum().map_err(TabbsError::Config)?;
+    match parser.tokens.get(parser.position) {
+        None => Ok(expr),
+        Some(token) => Err(TabbsError::Config(format!(
+            "Unexpected {} in expression: {}",
+            token, text
+        ))),
+    }
+}
+
+/// A lexical token of an expression.
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(f64),
+    Name(String),
+    Symbol(char),
+}
+
+impl std::fmt::Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Token::Number(value) => write!(f, "`{}`", value),
+            Token::Name(name) => write!(f, "`{}`", name),
+            Token::Symbol(c) => write!(f, "`{}`", c),
+        }
+    }
+}
+
+/// Split `text` into numbers, names and operator symbols.
+fn tokenize(text: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = text.char_indices().peekable();
+    while let Some(&(start, c)) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if "+-*/()".contains(c) {
+            tokens.push(Token::Symbol(c));
+            chars.next();
+        } else if c.is_ascii_digit() || c == '.' {
+            let mut end = start;
+            while let Some(&(i, c)) = chars.peek() {
+                if !(c.is_ascii_digit() || c == '.') {
+                    break;
+                }
+                end = i + c.len_utf8();
+                chars.next();
+            }
+            let number = &text[start..end];
+            let value = number
+                .parse()
+                .map_err(|_| format!("Invalid number in expression: {}", number))?;
+            tokens.push(Token::Number(value));
+        } else if c.is_alphanumeric() || c == '_' {
+            let mut end = start;
+            while let Some(&(i, c)) = chars.peek() {
+                if !(c.is_alphanumeric() || c == '_') {
+                    break;
+                }
+                end = i + c.len_utf8();
+                chars.next();
+            }
+            tokens.push(Token::Name(text[start..end].to_string()));
+        } else {
+            return Err(format!("Unexpected `{}` in expression: {}", c, text));
+        }
+    }
+    Ok(tokens)
+}
+
+/// A recursive descent parser over the tokens of an expression.
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl Parser {
+    /// Consume the next token if it is one of `symbols`.
+    fn symbol(&mut self, symbols: &str) -> Option<char> {
+        match self.tokens.get(self.position) {
+            Some(Token::Symbol(c)) if symbols.contains(*c) => {
+                self.position += 1;
+                Some(*c)
+            }
+            _ => None,
+        }
+    }
+
+    /// Parse terms joined by `+` and `-`.
+    fn sum(&mut self) -> Result<Expr, String> {
+        let mut expr = self.product()?;
+        while let Some(op) = self.symbol("+-") {
+            expr = Expr::Binary(op, Box::new(expr), Box::new(self.product()?));
+        }
+        Ok(expr)
+    }
+
+    /// Parse factors joined by `*` and `/`.
+    fn product(&mut self) -> Result<Expr, String> {
+        let mut expr = self.factor()?;
+        while let Some(op) = self.symbol("*/") {
+            expr = Expr::Binary(op, Box::new(expr), Box::new(self.factor()?));
+        }
+        Ok(expr)
+    }
+
+    /// Parse a number, a column name, a negation or a parenthesized sum.
+    fn factor(&mut self) -> Result<Expr, String> {
+        if self.symbol("-").is_some() {
+            return Ok(Expr::Neg(Box::new(self.factor()?)));
+        }
+        if self.symbol("(").is_some() {
+            let expr = self.sum()?;
+            return match self.symbol(")") {
+                Some(_) => Ok(expr),
+                None => Err("Missing `)` in expression".to_string()),
+            };
+        }
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        match token {
+            Some(Token::Number(value)) => Ok(Expr::Number(value)),
+            Some(Token::Name(name)) => Ok(Expr::Column(name)),
+            Some(token) => Err(format!("Unexpected {} in expression", token)),
+            None => Err("Unexpected end of expression".to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_compute() {
+        let (name, expr) = parse_compute("total = (price + 1) * qty - -2 / 4").unwrap();
+        assert_eq!(name, "total");
+        assert_eq!(expr.columns(), vec!["price", "qty"]);
+        let column = |name: &str| match name {
+            "price" => Some(2.5),
+            "qty" => Some(4.0),
+            _ => None,
+        };
+        assert_eq!(expr.eval(&column), Some(14.5));
+        assert_eq!(parse_expr("price / 0").unwrap().eval(&column), None);
+        assert_eq!(parse_expr("stock * 2").unwrap().eval(&column), None);
+
+        assert!(parse_compute("price * qty").is_err());
+        assert!(parse_expr("price *").is_err());
+        assert!(parse_expr("(price").is_err());
+        assert!(parse_expr("price % 2").is_err());
+    }
+
+    #[test]
+    fn test_parse_predicate() {
+        let predicate = parse_predicate("status==inactive").unwrap();
+        assert_eq!(predicate.column, "status");
+        assert_eq!(predicate.op, "==");
+        assert!(predicate.matches(" inactive "));
+        assert!(!predicate.matches("active"));
+
+        let predicate = parse_predicate("cpu >= 80").unwrap();
+        assert_eq!((predicate.column.as_str(), predicate.op), ("cpu", ">="));
+        assert!(predicate.matches("80.0"));
+        assert!(!predicate.matches("79"));
+        assert!(!predicate.matches("n/a"));
+        assert!(parse_predicate("cpu<1e3").unwrap().matches("999"));
+        assert!(parse_predicate("name!=").unwrap().matches("jack"));
+
+        assert!(parse_predicate("status").is_err());
+        assert!(parse_predicate("==x").is_err());
+        assert!(parse_predicate("a=b").is_err());
+    }
+}
@@ -0,0 +1,242 @@
+//! Opening of input sources named on the command line.
+
+use crate::error::TabbsError;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::thread;
+use std::time::Duration;
+
+/// How many bytes are read between updates of the progress indicator by default.
+pub const PROGRESS_INTERVAL: u64 = 1 << 20;
+
+/// How long a `TailReader` waits before checking a file for new data again.
+const TAIL_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Open `path` for reading, fetching it over the network when it is an `http://`
+/// URL. `https://` URLs are recognized but fail, as there is no TLS support. Any
+/// other path is opened as a local file.
+pub fn open_input(path: &str) -> Result<Box<dyn Read>, TabbsError> {
+    if is_url(path) {
+        return Ok(open_url(path)?);
+    }
+    Ok(Box::new(File::open(path)?))
+}
+
+/// Whether `path` names a remote resource rather than a local file.
+fn is_url(path: &str) -> bool {
+    path.starts_with("http://") || path.starts_with("https://")
+}
+
+#[cfg(feature = "remote")]
+fn open_url(url: &str) -> io::Result<Box<dyn Read>> {
+    let body = crate::remote::fetch(url)?;
+    Ok(Box::new(io::Cursor::new(body)))
+}
+
+#[cfg(not(feature = "remote"))]
+fn open_url(url: &str) -> io::Result<Box<dyn Read>> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        format!(
+            "cannot fetch {}: tabbs was built without the `remote` feature",
+            url
+        ),
+    ))
+}
+
+/// Keep only lines `first` to `last` of `reader`, counting from 1 and including both ends.
+///
+/// The first `header` lines are kept too, wherever the range starts, for inputs
+/// that begin with their column names. Lines before `first` are still read,
+/// since a line's offset is only known by scanning the lines before it, even in
+/// a file. Nothing after `last` is read, so a short range near the start of a
+/// huge input is cheap. Without `last`, every line from `first` on is kept.
+///
+pub fn take_lines(
+    reader: Box<dyn Read + '_>,
+    first: usize,
+    last: Option<usize>,
+    header: usize,
+) -> Result<Box<dyn Read>, TabbsError> {
+    let mut reader = BufReader::new(reader);
+    let mut kept = Vec::new();
+    let mut line = Vec::new();
+    let mut number = 0;
+    while last.is_none_or(|last| number < last.max(header)) {
+        line.clear();
+        if reader.read_until(b'\n', &mut line)? == 0 {
+            break;
+        }
+        number += 1;
+        if number <= header || (number >= first && last.is_none_or(|last| number <= last)) {
+            kept.extend_from_slice(&line);
+        }
+    }
+    Ok(Box::new(io::Cursor::new(kept)))
+}
+
+/// Wrap `reader` so that the number of bytes read is reported on `sink` every
+/// `interval` bytes, such as `PROGRESS_INTERVAL`.
+///
+/// Progress is only shown when `terminal` says that `sink` is a terminal;
+/// otherwise `reader` is returned unchanged.
+///
+pub fn with_progress<'a, W>(
+    reader: Box<dyn Read + 'a>,
+    sink: W,
+    terminal: bool,
+    interval: u64,
+) -> Box<dyn Read + 'a>
+where
+    W: Write + 'a,
+{
+    if !terminal {
+        return reader;
+    }
+    Box::new(ProgressReader::new(reader, sink, interval))
+}
+
+/// A reader that writes a running byte count to a sink as it is read.
+pub struct ProgressReader<R, W> {
+    inner: R,
+    sink: W,
+    interval: u64,
+    read: u64,
+    reported: u64,
+}
+
+impl<R: Read, W: Write> ProgressReader<R, W> {
+    /// Report progress on `sink` each time another `interval` bytes have been read.
+    pub fn new(inner: R, sink: W, interval: u64) -> Self {
+        ProgressReader {
+            inner,
+            sink,
+            interval,
+            read: 0,
+            reported: 0,
+        }
+    }
+}
+
+impl<R: Read, W: Write> Read for ProgressReader<R, W> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.read += n as u64;
+        if n == 0 {
+            if self.reported > 0 {
+                // Progress is best effort and must not fail the read.
+                let _ = writeln!(self.sink, "\rRead {:>10}", format_bytes(self.read));
+                self.reported = 0;
+            }
+        } else if self.read - self.reported >= self.interval {
+            let _ = write!(self.sink, "\rRead {:>10}", format_bytes(self.read));
+            let _ = self.sink.flush();
+            self.reported = self.read;
+        }
+        Ok(n)
+    }
+}
+
+/// A reader that waits for more data at the end of its input instead of
+/// reporting it, like `tail -f`, so that a file can be followed as it grows.
+pub struct TailReader<R> {
+    inner: R,
+}
+
+impl<R: Read> TailReader<R> {
+    pub fn new(inner: R) -> Self {
+        TailReader { inner }
+    }
+}
+
+impl<R: Read> Read for TailReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            let n = self.inner.read(buf)?;
+            if n > 0 || buf.is_empty() {
+                return Ok(n);
+            }
+            thread::sleep(TAIL_POLL_INTERVAL);
+        }
+    }
+}
+
+/// Format a byte count with a binary unit, such as `1.5 MiB`.
+fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit + 1 < UNITS.len() {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", value, UNITS[unit])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::IsTerminal;
+
+    #[test]
+    fn test_progress() {
+        let dir = std::env::temp_dir();
+        let input = dir.join(format!("tabbs-progress-input-{}", std::process::id()));
+        let stderr = dir.join(format!("tabbs-progress-stderr-{}", std::process::id()));
+        std::fs::write(&input, "jack,35\njane,50\n").unwrap();
+
+        let sink = File::create(&stderr).unwrap();
+        let terminal = sink.is_terminal();
+        let mut reader = with_progress(
+            open_input(input.to_str().unwrap()).unwrap(),
+            sink,
+            terminal,
+            PROGRESS_INTERVAL,
+        );
+        let mut text = String::new();
+        reader.read_to_string(&mut text).unwrap();
+        assert_eq!(text, "jack,35\njane,50\n");
+        assert_eq!(std::fs::read_to_string(&stderr).unwrap(), "");
+        std::fs::remove_file(input).unwrap();
+        std::fs::remove_file(stderr).unwrap();
+
+        let mut sink = Vec::new();
+        let mut reader = ProgressReader::new(&[0u8; 3000][..], &mut sink, 1024);
+        let mut buf = [0u8; 1000];
+        while reader.read(&mut buf).unwrap() > 0 {}
+        assert_eq!(
+            String::from_utf8(sink).unwrap(),
+            "\rRead    2.0 KiB\rRead    2.9 KiB\n"
+        );
+    }
+
+    #[test]
+    fn test_take_lines() {
+        let input: Box<dyn Read> = Box::new(&b"one\ntwo\nthree\nfour\nfive\n"[..]);
+        let mut text = String::new();
+        take_lines(input, 2, Some(3), 0)
+            .unwrap()
+            .read_to_string(&mut text)
+            .unwrap();
+        assert_eq!(text, "two\nthree\n");
+
+        let input: Box<dyn Read> = Box::new(&b"one\ntwo\nthree"[..]);
+        let mut text = String::new();
+        take_lines(input, 3, None, 0)
+            .unwrap()
+            .read_to_string(&mut text)
+            .unwrap();
+        assert_eq!(text, "three");
+
+        let input: Box<dyn Read> = Box::new(&b"sep=;\nname\njack\njane\nerin\n"[..]);
+        let mut text = String::new();
+        take_lines(input, 4, Some(4), 2)
+            .unwrap()
+            .read_to_string(&mut text)
+            .unwrap();
+        assert_eq!(text, "sep=;\nname\njane\n");
+    }
+}
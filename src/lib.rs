@@ -0,0 +1,43 @@
+//! The tabbs library parses delimited text into rows and renders them as tables.
+//!
+//! ```
+//! use tabbs::{parse_input, print_table_to_writer, ParseOptions, TableOptions};
+//!
+//! let input = "jack,35,neat\njane,50,cool\n";
+//! let (_, rows) = parse_input(input.as_bytes(), ',', &ParseOptions::default()).unwrap();
+//!
+//! let mut output = Vec::new();
+//! let options = TableOptions::default();
+//! print_table_to_writer(&["name", "age", "text"], &rows, &options, &mut output).unwrap();
+//! assert!(String::from_utf8(output).unwrap().contains("| jack | 35  | neat |"));
+//! ```
+
+#[cfg(feature = "clipboard")]
+pub mod clipboard;
+pub mod color;
+pub mod diff;
+pub mod error;
+pub mod expr;
+pub mod input;
+pub mod number;
+pub mod parse;
+#[cfg(feature = "remote")]
+pub mod remote;
+pub mod render;
+pub mod text;
+pub mod transform;
+pub mod types;
+
+pub use color::{parse_color, parse_color_scale, parse_value_colors, ColorScale, ValueColors};
+pub use error::TabbsError;
+pub use input::{open_input, take_lines, with_progress, TailReader, PROGRESS_INTERVAL};
+pub use number::{parse_number_formats, NumberFormat, NumberLocale};
+pub use parse::{
+    follow_rows, parse_fixed_width_input, parse_input, parse_kv_input, parse_nul_fields,
+    parse_numbered_input, parse_self_describing, render_csv, ParseOptions, ParsedInput,
+};
+pub use render::{
+    compute_column_widths, print_table_to_writer, render_at, render_streaming, side_by_side,
+    table_dimensions, write_column_metadata, Align, BorderChars, Format, TableOptions, Theme,
+};
+pub use types::{detect_column_types, ColumnType};
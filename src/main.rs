@@ -20,126 +20,1486 @@
 //! +------+-----+------+
 //! ```
 
-use colored::*;
+mod cli;
+mod profile;
+
+use cli::Args;
+use std::cell::RefCell;
 use std::env;
-use std::io::{self, Read, Write};
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, IsTerminal, Read, Write};
+use std::path::{Path, PathBuf};
 use std::process;
+use std::str::FromStr;
+use tabbs::color::is_known_color;
+use tabbs::diff::{diff_rows, RowStatus};
+use tabbs::expr::{parse_compute, parse_predicate};
+use tabbs::parse::sep_directive;
+use tabbs::text::{block_width, sanitize};
+use tabbs::transform::{
+    abbreviate_numbers, check_field_counts, compute_column, cumulative_column, ditto_columns,
+    drop_empty_columns, drop_empty_rows, expand_column, flatten_column, group_by, group_counts,
+    group_rows, histogram, insert_row_numbers, limit_rows, mask_columns, ragged_rows, repair_rows,
+    replace_booleans, rotate, round_numbers, sample_rows, sanitize_rows, select_columns,
+    sort_columns, sort_rows,
+};
+use tabbs::{
+    detect_column_types, follow_rows, open_input, parse_color, parse_color_scale,
+    parse_fixed_width_input, parse_input, parse_kv_input, parse_nul_fields, parse_number_formats,
+    parse_numbered_input, parse_self_describing, parse_value_colors, print_table_to_writer,
+    take_lines, with_progress, write_column_metadata, Align, BorderChars, ColumnType, Format,
+    ParseOptions, TabbsError, TableOptions, TailReader, Theme, PROGRESS_INTERVAL,
+};
+
+/// The number of rows sampled to infer column types for `--schema`.
+const SCHEMA_SAMPLE_ROWS: usize = 1000;
+
+/// Default total width used when neither `--width` nor `COLUMNS` is set.
+const DEFAULT_TERMINAL_WIDTH: usize = 80;
+
+/// The number of mask characters shown in place of each `--mask` cell.
+const MASK_LENGTH: usize = 4;
+
+/// Options that need the whole input at once and so cannot be used with `--follow`.
+const FOLLOW_CONFLICTS: [&str; 25] = [
+    "--input",
+    "--lines",
+    "--fixed-widths",
+    "--diff",
+    "--compute",
+    "--columns",
+    "--sort-columns",
+    "--schema",
+    "--split-by",
+    "--clipboard",
+    "--sort",
+    "--orig-index",
+    "--histogram",
+    "--rotate",
+    "--record-sep",
+    "--cumulative",
+    "--dim",
+    "--sample",
+    "--group-by",
+    "--stdin0",
+    "--toc",
+    "--strict",
+    "--self-describe",
+    "--meta-out",
+    "--drop-empty-cols",
+];
+
+/// Options that cannot be combined with `--show-bad-rows`.
+const SHOW_BAD_ROWS_CONFLICTS: [&str; 3] = ["--follow", "--fixed-widths", "--diff"];
 
-/// The main function reads the command line arguments and standard input,
-/// then calls the `print_table_to_writer` function to print the table to stdout.
+/// The main function runs tabbs with the command line arguments and the standard
+/// streams, exiting with the code it returns.
 ///
 fn main() {
-    let args: Vec<String> = env::args().collect();
-    if args.len() < 3 || args[1] != "-c" {
-        eprintln!(
-            "Usage: tabb -c \"column1,column2,...\" [--header-color COLOR] [--cell-color COLOR]"
-        );
-        process::exit(1);
+    let args: Vec<String> = env::args().skip(1).collect();
+    let terminals = Terminals {
+        stdout: io::stdout().is_terminal(),
+        stderr: io::stderr().is_terminal(),
+    };
+    let code = run(
+        &args,
+        io::stdin(),
+        &mut io::stdout().lock(),
+        &mut io::stderr(),
+        terminals,
+        PROGRESS_INTERVAL,
+    );
+    if code != 0 {
+        process::exit(code);
+    }
+}
+
+/// Run tabbs with the command line arguments `args`, excluding the program name,
+/// and return the process exit code.
+///
+/// The input is read from `stdin` unless `-f` names a file, the table is written
+/// to `stdout`, and warnings, progress and errors go to `stderr`. `terminals`
+/// tells whether the two outputs are terminals, and the progress of reading a
+/// file is updated every `progress_interval` bytes.
+///
+fn run(
+    args: &[String],
+    stdin: impl Read + 'static,
+    stdout: &mut dyn Write,
+    stderr: &mut dyn Write,
+    terminals: Terminals,
+    progress_interval: u64,
+) -> i32 {
+    let result = try_run(
+        args,
+        Box::new(stdin),
+        &mut *stdout,
+        &mut *stderr,
+        terminals,
+        progress_interval,
+    )
+    .and_then(|()| stdout.flush().map_err(TabbsError::from));
+    match result {
+        Ok(()) => 0,
+        Err(err) => {
+            let _ = writeln!(stderr, "{}", err);
+            exit_code(&err)
+        }
+    }
+}
+
+/// Whether the outputs given to `run` are terminals.
+///
+/// A terminal stdout gets colors, sanitized input and `--follow` tables redrawn
+/// in place, and a terminal stderr gets the progress of reading a file.
+///
+#[derive(Debug, Default, Clone, Copy)]
+struct Terminals {
+    stdout: bool,
+    stderr: bool,
+}
+
+/// Return the process exit code for `err`, following the BSD `sysexits.h` codes.
+fn exit_code(err: &TabbsError) -> i32 {
+    match err {
+        TabbsError::Config(_) => 64,
+        TabbsError::Parse { .. } => 65,
+        TabbsError::Io(_) => 74,
+    }
+}
+
+/// Return a usage error combining `message` with the usage summary.
+fn usage_error(message: &str) -> TabbsError {
+    let message = match message {
+        "" => format!("{}\nTry --help for more information.", cli::USAGE),
+        _ => format!(
+            "{}\n{}\nTry --help for more information.",
+            message,
+            cli::USAGE
+        ),
+    };
+    TabbsError::Config(message)
+}
+
+/// Prefix the message of an I/O error with `context`.
+fn io_context(err: TabbsError, context: &str) -> TabbsError {
+    match err {
+        TabbsError::Io(err) => {
+            TabbsError::Io(io::Error::new(err.kind(), format!("{}: {}", context, err)))
+        }
+        other => other,
+    }
+}
+
+/// How much diagnostic output is written besides the table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Verbosity {
+    /// Only errors.
+    Quiet,
+    /// Warnings about changes made to the input, such as repaired rows.
+    Normal,
+    /// Warnings and notes explaining how the table was built.
+    Verbose,
+}
+
+/// Writes warnings and diagnostic notes to a sink, usually stderr, according to a `Verbosity`.
+struct Reporter<W> {
+    verbosity: Verbosity,
+    sink: W,
+}
+
+impl<W: Write> Reporter<W> {
+    fn new(verbosity: Verbosity, sink: W) -> Self {
+        Reporter { verbosity, sink }
+    }
+
+    /// Report a change made to the input, unless quiet.
+    fn warn(&mut self, message: &str) {
+        if self.verbosity >= Verbosity::Normal {
+            // Diagnostics are best effort and must not fail the table.
+            let _ = writeln!(self.sink, "{}", message);
+        }
+    }
+
+    /// Report a detail of how the table was built, only when verbose.
+    fn note(&mut self, message: &str) {
+        if self.verbosity >= Verbosity::Verbose {
+            let _ = writeln!(self.sink, "note: {}", message);
+        }
+    }
+}
+
+/// A writer that the `Reporter` and the progress of reading the input can share.
+struct SharedWriter<'a>(RefCell<&'a mut dyn Write>);
+
+impl Write for &SharedWriter<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.borrow_mut().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.borrow_mut().flush()
+    }
+}
+
+/// Describe the delimiter in use for a `--verbose` note.
+fn delimiter_note(delimiter: char, explicit: bool) -> String {
+    let name = match delimiter {
+        '\t' => "tab".to_string(),
+        ' ' => "whitespace".to_string(),
+        other => format!("'{}'", other),
+    };
+    let source = if explicit { "--delimiter" } else { "default" };
+    format!("splitting fields on {} ({})", name, source)
+}
+
+/// Parse the value of option `name`, if it was given.
+fn parse_option<T: FromStr>(args: &Args, name: &str) -> Result<Option<T>, TabbsError> {
+    args.value(name)
+        .map(|value| {
+            value
+                .parse()
+                .map_err(|_| TabbsError::Config(format!("Invalid {} value: {}", name, value)))
+        })
+        .transpose()
+}
+
+/// Run tabbs like `run`, returning the error that ends it instead of an exit code.
+fn try_run(
+    args: &[String],
+    stdin: Box<dyn Read>,
+    mut stdout: &mut dyn Write,
+    stderr: &mut dyn Write,
+    terminals: Terminals,
+    progress_interval: u64,
+) -> Result<(), TabbsError> {
+    let args = Args::parse(args).map_err(|err| usage_error(&err))?;
+    if args.flag("-h") {
+        cli::write_help(&mut stdout)?;
+        return Ok(());
+    }
+    if args.flag("-V") {
+        cli::write_version(&mut stdout)?;
+        return Ok(());
+    }
+    let args = match args.value("--profile").map(PathBuf::from) {
+        Some(path) => args.with_defaults(profile::load_profile(&path)?),
+        None => args,
+    };
+    let kv_input = match args.value("--input") {
+        None | Some("csv") => false,
+        Some("kv") => true,
+        Some(other) => {
+            return Err(TabbsError::Config(format!(
+                "Unknown input format: {}",
+                other
+            )))
+        }
+    };
+    if args.flag("--quiet") && args.flag("--verbose") {
+        return Err(TabbsError::Config(
+            "--quiet and --verbose cannot be combined".to_string(),
+        ));
+    }
+    let verbosity = if args.flag("--quiet") {
+        Verbosity::Quiet
+    } else if args.flag("--verbose") {
+        Verbosity::Verbose
+    } else {
+        Verbosity::Normal
+    };
+    let stderr = SharedWriter(RefCell::new(stderr));
+    let mut reporter = Reporter::new(verbosity, &stderr);
+    if args.flag("-c") && args.flag("--columns-file") {
+        return Err(TabbsError::Config(
+            "-c and --columns-file cannot be combined".to_string(),
+        ));
+    }
+    let self_describe = args.flag("--self-describe");
+    if !kv_input && !self_describe && !args.flag("-c") && !args.flag("--columns-file") {
+        return Err(usage_error(""));
+    }
+    let follow = args.flag("--follow");
+    if let Some(option) = FOLLOW_CONFLICTS
+        .iter()
+        .find(|option| follow && args.flag(option))
+    {
+        return Err(TabbsError::Config(format!(
+            "--follow cannot be combined with {}",
+            option
+        )));
+    }
+
+    if args.flag("--show-bad-rows") {
+        let conflict = SHOW_BAD_ROWS_CONFLICTS
+            .iter()
+            .copied()
+            .find(|option| args.flag(option))
+            .or(kv_input.then_some("--input kv"));
+        if let Some(option) = conflict {
+            return Err(TabbsError::Config(format!(
+                "--show-bad-rows cannot be combined with {}",
+                option
+            )));
+        }
+    }
+
+    colored::control::set_override(color_enabled(|name| env::var(name).ok(), terminals.stdout));
+    let theme: Option<Theme> = parse_option(&args, "--theme")?;
+    let header_color = match args.value("--header-color") {
+        Some(color) => Some(parse_color(color)?),
+        None => theme
+            .and_then(|theme| theme.header_color)
+            .map(str::to_string),
+    };
+    let stripe_color = match args.value("--stripe") {
+        Some(color) => Some(parse_color(color)?),
+        None => theme
+            .and_then(|theme| theme.stripe_color)
+            .map(str::to_string),
+    };
+    let cell_color = args.value("--cell-color").map(parse_color).transpose()?;
+    let alt_column_color = args
+        .value("--alt-column-color")
+        .map(parse_color)
+        .transpose()?;
+
+    let total_width = parse_option(&args, "--width")?.unwrap_or_else(terminal_width);
+    let format = match args.value("--format") {
+        None | Some("table") => Format::Table,
+        Some("plain") => Format::Plain,
+        Some("aligned-tsv") => Format::AlignedTsv,
+        Some("latex") => Format::Latex,
+        Some("jira") => Format::Jira,
+        Some("json") => Format::Json,
+        Some(other) => {
+            return Err(TabbsError::Config(format!(
+                "Unknown output format: {}",
+                other
+            )))
+        }
+    };
+    let jobs = parse_option(&args, "--jobs")?.unwrap_or(1);
+    let limit: Option<usize> = parse_option(&args, "--limit")?;
+    let number_formats = args
+        .value("--num-format")
+        .map_or(Ok(Vec::new()), parse_number_formats)?;
+    let prefixes = args
+        .values("--prefix")
+        .into_iter()
+        .map(|spec| parse_affix(spec, "--prefix"))
+        .collect::<Result<Vec<_>, _>>()?;
+    let suffixes = args
+        .values("--suffix")
+        .into_iter()
+        .map(|spec| parse_affix(spec, "--suffix"))
+        .collect::<Result<Vec<_>, _>>()?;
+    let default_border = theme.map_or(BorderChars::ASCII, |theme| theme.border);
+    let border = args
+        .value("--border")
+        .map_or(Ok(default_border), str::parse)?;
+    let border = border_for_locale(
+        border,
+        locale_is_utf8(|name| env::var(name).ok()),
+        args.flag("--force-unicode"),
+    );
+    let header_wrap: Option<usize> = parse_option(&args, "--wrap-headers")?;
+    if header_wrap == Some(0) {
+        return Err(TabbsError::Config(
+            "Invalid --wrap-headers value: 0".to_string(),
+        ));
+    }
+    let round: Option<usize> = parse_option(&args, "--round")?;
+    let frozen_columns = parse_option(&args, "--freeze")?.unwrap_or(0);
+    let caption_align = parse_option(&args, "--caption-align")?.unwrap_or_default();
+    let truncate_ratio: f64 = parse_option(&args, "--truncate-ratio")?.unwrap_or(0.5);
+    if !(0.0..=1.0).contains(&truncate_ratio) {
+        return Err(TabbsError::Config(format!(
+            "Invalid --truncate-ratio value: {}",
+            truncate_ratio
+        )));
+    }
+    let col_width_specs = args
+        .value("--col-widths")
+        .map(parse_col_widths)
+        .transpose()?;
+    let max_col_width_specs = args
+        .value("--max-col-widths")
+        .map(parse_col_widths)
+        .transpose()?;
+
+    let color_scales = args
+        .values("--color-scale")
+        .into_iter()
+        .map(parse_color_scale)
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let value_colors = args
+        .values("--value-color")
+        .into_iter()
+        .map(parse_value_colors)
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let delimiter = args
+        .value("--delimiter")
+        .map_or(Ok(','), |value| parse_char(value, "delimiter"))?;
+    let comment = args
+        .value("--comment")
+        .map(|value| parse_char(value, "comment marker"))
+        .transpose()?;
+    let parse_options = ParseOptions {
+        comment,
+        trim: !args.flag("--no-trim"),
+        backslash_escape: args.flag("--backslash-escape"),
+        blank_as_empty: args.flag("--blank-as-empty"),
+        record_separator: args
+            .value("--record-sep")
+            .map_or(Ok('\n'), |value| parse_char(value, "record separator"))?,
+        ..ParseOptions::default()
+    };
+
+    let auto_align =
+        args.flag("--auto-align") || matches!(args.value("--align"), None | Some("auto"));
+    let align = match args.value("--align") {
+        None | Some("auto") => Align::Left,
+        Some(value) => value.parse()?,
+    };
+    let column_entries: Option<Vec<String>> = match args.value("--columns-file") {
+        Some(path) => Some(read_columns_file(path)?),
+        None => args
+            .value("-c")
+            .map(|spec| spec.split(',').map(str::to_string).collect()),
+    };
+    let column_specs: Option<Vec<ColumnSpec>> = column_entries
+        .map(|entries| {
+            entries
+                .iter()
+                .map(|entry| parse_column_spec(entry))
+                .collect()
+        })
+        .transpose()?;
+    let specified_columns = column_specs.as_ref().map(|specs| {
+        specs
+            .iter()
+            .map(|spec| spec.name.clone())
+            .collect::<Vec<String>>()
+    });
+
+    let fixed_widths: Option<Vec<usize>> = args
+        .value("--fixed-widths")
+        .map(parse_fixed_widths)
+        .transpose()?;
+
+    let reader: Box<dyn Read + '_> = match args.value("-f") {
+        Some(path) => {
+            let reader = open_input(path)
+                .map_err(|err| io_context(err, &format!("Failed to open {}", path)))?;
+            if follow {
+                Box::new(TailReader::new(reader))
+            } else if args.flag("--quiet") {
+                reader
+            } else {
+                with_progress(reader, &stderr, terminals.stderr, progress_interval)
+            }
+        }
+        None => stdin,
+    };
+    let parse_records = |reader: Box<dyn Read + '_>| {
+        let parsed = match &fixed_widths {
+            Some(widths) => parse_fixed_width_input(reader, widths, &parse_options),
+            None => parse_input(reader, delimiter, &parse_options),
+        };
+        parsed
+            .map(|(_, rows)| rows)
+            .map_err(|err| io_context(err, "Failed to read input"))
+    };
+    let line_range = args.value("--lines").map(parse_line_range).transpose()?;
+    let reader = match line_range {
+        Some((first, last)) => {
+            // A self-describing header, and the `sep=` line above it, come along
+            // with any range.
+            let (reader, header) = if self_describe {
+                let mut reader = BufReader::new(reader);
+                let mut first_line = String::new();
+                reader
+                    .read_line(&mut first_line)
+                    .map_err(|err| io_context(err.into(), "Failed to read input"))?;
+                let header = 1 + usize::from(sep_directive(&first_line).is_some());
+                let reader: Box<dyn Read + '_> =
+                    Box::new(io::Cursor::new(first_line).chain(reader));
+                (reader, header)
+            } else {
+                (reader, 0)
+            };
+            take_lines(reader, first, last, header)
+                .map_err(|err| io_context(err, "Failed to read input"))?
+        }
+        None => reader,
+    };
+    if !kv_input && !self_describe && fixed_widths.is_none() && !args.flag("--stdin0") {
+        reporter.note(&delimiter_note(delimiter, args.flag("--delimiter")));
+    }
+    let mut follow_reader = None;
+    let (mut column_names, mut rows) = if follow {
+        follow_reader = Some(reader);
+        (specified_columns.unwrap_or_default(), Vec::new())
+    } else if kv_input {
+        let (keys, mut rows) =
+            parse_kv_input(reader).map_err(|err| io_context(err, "Failed to read input"))?;
+        let mut keys = keys.unwrap_or_default();
+        if let Some(columns) = &specified_columns {
+            let names: Vec<&str> = columns.iter().map(|s| s.as_str()).collect();
+            select_columns(&mut keys, &mut rows, &names)?;
+        }
+        (keys, rows)
+    } else if self_describe {
+        let (_, (header, mut rows)) = parse_self_describing(reader, delimiter, &parse_options)
+            .map_err(|err| io_context(err, "Failed to read input"))?;
+        let mut names = header.unwrap_or_default();
+        if let Some(columns) = &specified_columns {
+            let columns: Vec<&str> = columns.iter().map(|s| s.as_str()).collect();
+            select_columns(&mut names, &mut rows, &columns)?;
+        }
+        (names, rows)
+    } else if args.flag("--stdin0") {
+        let names = specified_columns
+            .ok_or_else(|| TabbsError::Config("--stdin0 requires -c".to_string()))?;
+        let rows = parse_nul_fields(reader, names.len())
+            .map_err(|err| io_context(err, "Failed to read input"))?;
+        (names, rows)
+    } else if args.flag("--show-bad-rows") {
+        let offset = line_range.map_or(0, |(first, _)| first - 1);
+        let records = parse_numbered_input(reader, delimiter, &parse_options)
+            .map_err(|err| io_context(err, "Failed to read input"))?
+            .into_iter()
+            .map(|(line, record)| (line + offset, record))
+            .collect();
+        let mut names = specified_columns.unwrap_or_default();
+        let rows = ragged_rows(records, names.len());
+        names.splice(0..0, ["line".to_string(), "fields".to_string()]);
+        (names, rows)
+    } else if args.flag("--strict") && fixed_widths.is_none() {
+        let offset = line_range.map_or(0, |(first, _)| first - 1);
+        let records: Vec<(usize, Vec<String>)> =
+            parse_numbered_input(reader, delimiter, &parse_options)
+                .map_err(|err| io_context(err, "Failed to read input"))?
+                .into_iter()
+                .map(|(line, record)| (line + offset, record))
+                .collect();
+        let names = specified_columns.unwrap_or_default();
+        check_field_counts(&records, names.len())?;
+        (
+            names,
+            records.into_iter().map(|(_, record)| record).collect(),
+        )
+    } else {
+        (
+            specified_columns.unwrap_or_default(),
+            parse_records(reader)?,
+        )
+    };
+
+    let mut row_colors = Vec::new();
+    let mut cell_colors = Vec::new();
+    if let Some(path) = args.value("--diff") {
+        if kv_input {
+            return Err(TabbsError::Config(
+                "--diff is not supported with --input kv".to_string(),
+            ));
+        }
+        let key = match args.value("--key") {
+            Some(name) => column_names
+                .iter()
+                .position(|column| column == name)
+                .ok_or_else(|| TabbsError::Config(format!("Unknown column: {}", name)))?,
+            None => 0,
+        };
+        let other =
+            open_input(path).map_err(|err| io_context(err, &format!("Failed to open {}", path)))?;
+        let diff = diff_rows(&rows, &parse_records(other)?, key)?;
+        column_names.insert(0, "status".to_string());
+        // Changed rows only color their status and the cells that changed.
+        for row in &diff {
+            let color = row.status.color().map(str::to_string);
+            if row.status == RowStatus::Changed {
+                let changed = std::iter::once(&true).chain(&row.changed);
+                cell_colors.push(
+                    changed
+                        .map(|&changed| color.clone().filter(|_| changed))
+                        .collect(),
+                );
+                row_colors.push(None);
+            } else {
+                cell_colors.push(Vec::new());
+                row_colors.push(color);
+            }
+        }
+        rows = diff
+            .into_iter()
+            .map(|mut row| {
+                row.cells.insert(0, row.status.marker().to_string());
+                row.cells
+            })
+            .collect();
     }
 
-    let column_names: Vec<&str> = args[2].split(',').collect();
+    let sanitize_input =
+        !args.flag("--no-sanitize-input") && (args.flag("--sanitize-input") || terminals.stdout);
+    if sanitize_input {
+        sanitize_rows(&mut rows);
+        for name in column_names.iter_mut() {
+            *name = sanitize(name);
+        }
+    }
+
+    if args.flag("--repair") {
+        let repaired = repair_rows(&mut rows, column_names.len());
+        if repaired > 0 {
+            reporter.warn(&format!(
+                "Repaired {} of {} rows to {} fields",
+                repaired,
+                rows.len(),
+                column_names.len()
+            ));
+        }
+    }
+
+    if args.flag("--drop-empty-rows") {
+        let dropped = drop_empty_rows(&mut rows);
+        reporter.note(&format!("dropped {} empty rows", dropped));
+    }
+
+    for spec in args.values("--compute") {
+        let (name, expr) = parse_compute(spec)?;
+        compute_column(&mut column_names, &mut rows, &name, &expr)?;
+    }
+    if let Some(names) = args.value("--columns") {
+        let names: Vec<&str> = names.split(',').collect();
+        select_columns(&mut column_names, &mut rows, &names)?;
+    }
+    if args.flag("--sort-columns") {
+        sort_columns(&mut column_names, &mut rows);
+    }
+    if let Some(name) = args.value("--histogram") {
+        histogram(&mut column_names, &mut rows, name)?;
+    }
+    let mut origins: Vec<usize> = (0..rows.len()).collect();
+    if let Some(count) = parse_option(&args, "--sample")? {
+        let seed = match parse_option(&args, "--seed")? {
+            Some(seed) => seed,
+            None => random_seed(),
+        };
+        origins = sample_rows(&mut rows, count, seed);
+    } else if args.flag("--seed") {
+        return Err(TabbsError::Config("--seed requires --sample".to_string()));
+    }
+    if let Some(name) = args.value("--sort") {
+        let order = sort_rows(&column_names, &mut rows, name)?;
+        origins = order.iter().map(|&i| origins[i]).collect();
+    }
+    let group_column = args.value("--group-by");
+    let mut toc = None;
+    if let Some(name) = group_column {
+        let order = group_by(&column_names, &mut rows, name)?;
+        origins = order.iter().map(|&i| origins[i]).collect();
+        if args.flag("--toc") {
+            let index = column_names.iter().position(|column| column == name);
+            toc = index.map(|index| (name, group_counts(&rows, index)));
+        }
+    } else if let Some(flag) = ["--group-lines", "--toc"]
+        .into_iter()
+        .find(|&f| args.flag(f))
+    {
+        return Err(TabbsError::Config(format!("{} requires --group-by", flag)));
+    }
+    if let Some(name) = args.value("--cumulative") {
+        cumulative_column(
+            &mut column_names,
+            &mut rows,
+            name,
+            args.flag("--cumulative-reset"),
+        )?;
+    }
+    transform_cells(&args, &column_names, &mut rows)?;
+
+    let omitted_rows = limit.map_or(0, |limit| limit_rows(&mut rows, limit));
+    if omitted_rows > 0 {
+        reporter.note(&format!("left out {} rows beyond --limit", omitted_rows));
+    }
+    if args.flag("--drop-empty-cols") {
+        let dropped = drop_empty_columns(&mut column_names, &mut rows);
+        reporter.note(&format!("dropped {} empty columns", dropped));
+    }
+
+    let spec_for = |name: &String| {
+        column_specs
+            .iter()
+            .flatten()
+            .find(|spec| &spec.name == name)
+    };
+    let text_columns: Vec<&str> = args
+        .value("--text-columns")
+        .map_or_else(Vec::new, |names| names.split(',').collect());
+    // Value transforms see the input columns, before `#` is added or the table rotated.
+    let input_types: Vec<Option<ColumnType>> = column_names
+        .iter()
+        .map(|name| {
+            if text_columns.contains(&name.as_str()) {
+                Some(ColumnType::Text)
+            } else {
+                spec_for(name).and_then(|spec| spec.kind)
+            }
+        })
+        .collect();
+    if args.flag("--abbrev-numbers") {
+        abbreviate_numbers(&mut rows, &input_types, round);
+    }
+    if let Some(places) = round {
+        round_numbers(&mut rows, &input_types, places);
+    }
+    if args.flag("--bool-symbols") {
+        let yes = args.value("--true-symbol").unwrap_or("✓");
+        let no = args.value("--false-symbol").unwrap_or("✗");
+        replace_booleans(&mut rows, &input_types, yes, no);
+    }
+    if args.flag("--number") {
+        let numbers: Vec<usize> = if args.flag("--orig-index") {
+            origins.iter().map(|origin| origin + 1).collect()
+        } else {
+            (1..=rows.len()).collect()
+        };
+        insert_row_numbers(&mut column_names, &mut rows, &numbers);
+    } else if args.flag("--orig-index") {
+        return Err(TabbsError::Config(
+            "--orig-index requires --number".to_string(),
+        ));
+    }
+    if args.flag("--rotate") {
+        rotate(&mut column_names, &mut rows);
+    }
+
+    let dimmed_rows = match args.value("--dim") {
+        Some(spec) => {
+            let predicate = parse_predicate(spec)?;
+            let index = column_names
+                .iter()
+                .position(|name| *name == predicate.column)
+                .ok_or_else(|| {
+                    TabbsError::Config(format!("Unknown column: {}", predicate.column))
+                })?;
+            rows.iter()
+                .map(|row| row.get(index).is_some_and(|cell| predicate.matches(cell)))
+                .collect()
+        }
+        None => Vec::new(),
+    };
+    let column_widths = col_width_specs
+        .map(|specs| resolve_col_widths(&specs, total_width, column_names.len()))
+        .unwrap_or_default();
+    let max_column_widths = max_col_width_specs
+        .map(|specs| resolve_col_widths(&specs, total_width, column_names.len()))
+        .unwrap_or_default();
 
-    let header_color = args
+    let column_alignments = column_names
         .iter()
-        .position(|arg| arg == "--header-color")
-        .and_then(|pos| args.get(pos + 1).map(|s| s.to_owned()));
-    let cell_color = args
+        .map(|name| spec_for(name).and_then(|spec| spec.align))
+        .collect();
+    let mut column_types: Vec<Option<ColumnType>> = column_names
         .iter()
-        .position(|arg| arg == "--cell-color")
-        .and_then(|pos| args.get(pos + 1).map(|s| s.to_owned()));
-
-    let stdin = io::stdin();
-    let mut input = String::new();
-    stdin
-        .lock()
-        .read_to_string(&mut input)
-        .expect("Failed to read input");
-
-    let rows: Vec<Vec<String>> = input
-        .split_whitespace()
-        .map(|line| line.split(',').map(|s| s.trim().to_string()).collect())
+        .map(|name| spec_for(name).and_then(|spec| spec.kind))
         .collect();
+    if args.value("--text-columns").is_some() {
+        mark_text_columns(&column_names, &mut column_types, &text_columns)?;
+    }
+
+    let hidden_headers: Vec<String> = match args.value("--hide-header") {
+        Some(names) => names.split(',').map(str::to_string).collect(),
+        None => Vec::new(),
+    };
+    if let Some(name) = hidden_headers
+        .iter()
+        .find(|name| !column_names.contains(name))
+    {
+        return Err(TabbsError::Config(format!("Unknown column: {}", name)));
+    }
+
+    let options = TableOptions {
+        format,
+        header_color,
+        cell_color,
+        stripe_color,
+        alt_column_color,
+        column_widths,
+        max_column_widths,
+        hidden_headers,
+        header_wrap,
+        border,
+        row_header: args.flag("--row-header"),
+        outer_only: args.flag("--outer-only"),
+        no_header_rule: args.flag("--no-header-rule"),
+        align,
+        column_alignments,
+        auto_align,
+        column_types: column_types.clone(),
+        color_scales,
+        value_colors,
+        group_lines: group_column
+            .filter(|_| args.flag("--group-lines"))
+            .map(str::to_string),
+        row_colors,
+        cell_colors,
+        dimmed_rows,
+        number_formats,
+        heatmaps: args
+            .value("--heatmap")
+            .map(|names| names.split(',').map(str::to_string).collect())
+            .unwrap_or_default(),
+        prefixes,
+        suffixes,
+        locale: parse_option(&args, "--locale")?,
+        legend: args.flag("--legend"),
+        marks: args.flag("--marks"),
+        empty_message: args.value("--empty-message").map(str::to_string),
+        omitted_rows,
+        rtrim_for_width: args.flag("--rtrim-for-width"),
+        no_trailing_newline: args.flag("--no-trailing-newline"),
+        jobs,
+        banner: args.value("--banner").map(str::to_string),
+        caption: args.value("--caption").map(str::to_string),
+        caption_align,
+        hyperlinks: args.flag("--hyperlinks"),
+        pad_to: parse_option(&args, "--pad-to")?,
+        page_width: if args.flag("--page-columns") {
+            Some(total_width)
+        } else {
+            None
+        },
+        frozen_columns,
+        json_typed: args.flag("--json-typed"),
+        truncate_middle: args.flag("--truncate-middle").then_some(truncate_ratio),
+    };
+
+    if args.flag("--strict") {
+        check_strict(&column_names, &rows, &options)?;
+    }
+    if let Some(path) = args.value("--meta-out") {
+        let names: Vec<&str> = column_names.iter().map(|s| s.as_str()).collect();
+        let mut file = File::create(path)
+            .map_err(|err| io_context(err.into(), &format!("Failed to create {}", path)))?;
+        write_column_metadata(&names, &rows, &options, &mut file)?;
+    }
+
+    if args.flag("--schema") {
+        let (schema_columns, schema_rows) = schema_table(&column_names, &rows, &column_types);
+        let schema_options = TableOptions {
+            column_widths: Vec::new(),
+            max_column_widths: Vec::new(),
+            column_alignments: Vec::new(),
+            column_types: Vec::new(),
+            color_scales: Vec::new(),
+            ..options
+        };
+        return print_table_to_writer(&schema_columns, &schema_rows, &schema_options, &mut stdout);
+    }
+    if let Some(column) = args.value("--split-by") {
+        let out_dir = args
+            .value("--out-dir")
+            .ok_or_else(|| TabbsError::Config("--split-by requires --out-dir".to_string()))?;
+        let index = column_names
+            .iter()
+            .position(|name| name == column)
+            .ok_or_else(|| TabbsError::Config(format!("Unknown column: {}", column)))?;
+        colored::control::set_override(false);
+        let group_options = TableOptions {
+            row_colors: Vec::new(),
+            cell_colors: Vec::new(),
+            omitted_rows: 0,
+            ..options
+        };
+        let groups = group_rows(&rows, index);
+        write_groups(Path::new(out_dir), &column_names, &groups, &group_options)?;
+        return Ok(());
+    }
+    if let Some(reader) = follow_reader {
+        let names: Vec<&str> = column_names.iter().map(|s| s.as_str()).collect();
+        // The `#` column of `--number` is already named but not yet in the rows.
+        let input_names = &column_names[usize::from(args.flag("--number"))..];
+        let prepare =
+            |rows: &[Vec<String>], total: usize| -> Result<Vec<Vec<String>>, TabbsError> {
+                // With `--limit`, the rows kept are the last of all those seen so far.
+                let first = total - rows.len() + 1;
+                let mut rows = rows.to_vec();
+                if sanitize_input {
+                    sanitize_rows(&mut rows);
+                }
+                if args.flag("--repair") {
+                    repair_rows(&mut rows, input_names.len());
+                }
+                if args.flag("--drop-empty-rows") {
+                    drop_empty_rows(&mut rows);
+                }
+                transform_cells(&args, input_names, &mut rows)?;
+                if args.flag("--abbrev-numbers") {
+                    abbreviate_numbers(&mut rows, &input_types, round);
+                }
+                if let Some(places) = round {
+                    round_numbers(&mut rows, &input_types, places);
+                }
+                if args.flag("--bool-symbols") {
+                    let yes = args.value("--true-symbol").unwrap_or("✓");
+                    let no = args.value("--false-symbol").unwrap_or("✗");
+                    replace_booleans(&mut rows, &input_types, yes, no);
+                }
+                if args.flag("--number") {
+                    for (number, row) in (first..).zip(rows.iter_mut()) {
+                        row.insert(0, number.to_string());
+                    }
+                }
+                Ok(rows)
+            };
+        return follow_table(
+            reader,
+            delimiter,
+            &parse_options,
+            limit,
+            stdout,
+            terminals.stdout,
+            |rows, total, output| {
+                print_table_to_writer(&names, &prepare(rows, total)?, &options, output)
+            },
+        );
+    }
+    let column_names: Vec<&str> = column_names.iter().map(|s| s.as_str()).collect();
+    if args.flag("--clipboard") {
+        colored::control::set_override(false);
+        let mut output = Vec::new();
+        print_table_to_writer(&column_names, &rows, &options, &mut output)?;
+        copy_to_clipboard(&String::from_utf8_lossy(&output))?;
+        return Ok(());
+    }
+    if let Some((name, counts)) = toc {
+        write_toc(name, &counts, &options, &mut stdout)?;
+    }
+    print_table_to_writer(&column_names, &rows, &options, &mut stdout)
+}
 
-    let stdout = io::stdout();
-    let mut handle = stdout.lock();
-    print_table_to_writer(
-        &column_names,
-        &rows,
-        header_color.as_deref(),
-        cell_color.as_deref(),
-        &mut handle,
+/// Return an error for the first anomaly that would otherwise be shown silently:
+/// a color that is not known, a row whose cells do not match the columns, or a
+/// cell truncated to a fixed or maximum column width.
+fn check_strict(
+    column_names: &[String],
+    rows: &[Vec<String>],
+    options: &TableOptions,
+) -> Result<(), TabbsError> {
+    let colors = [
+        &options.header_color,
+        &options.cell_color,
+        &options.stripe_color,
+        &options.alt_column_color,
+    ]
+    .into_iter()
+    .flatten()
+    .chain(options.color_scales.iter().flat_map(|scale| {
+        scale
+            .thresholds
+            .iter()
+            .map(|(_, color)| color)
+            .chain([&scale.default])
+    }))
+    .chain(
+        options
+            .value_colors
+            .iter()
+            .flat_map(|rule| rule.colors.iter().map(|(_, color)| color)),
     );
+    for color in colors {
+        if !is_known_color(color) {
+            return Err(TabbsError::Config(format!("Unknown color: {}", color)));
+        }
+    }
+    for (r, row) in rows.iter().enumerate() {
+        if row.len() != column_names.len() {
+            return Err(TabbsError::Config(format!(
+                "Row {} has {} cells for {} columns",
+                r + 1,
+                row.len(),
+                column_names.len()
+            )));
+        }
+        for (i, cell) in row.iter().enumerate() {
+            let fixed = options.column_widths.get(i).copied().flatten();
+            let cap = options.max_column_widths.get(i).copied().flatten();
+            if let Some(width) = fixed.or(cap).filter(|&width| block_width(cell) > width) {
+                return Err(TabbsError::Config(format!(
+                    "Row {} of column {} does not fit its width of {}",
+                    r + 1,
+                    column_names[i],
+                    width
+                )));
+            }
+        }
+    }
+    Ok(())
 }
 
-/// Print a table with the given column names, row data, optional header and cell colors to the provided writer.
-///
-/// # Arguments
+/// Print an index of the groups of the `name` column and their row counts,
+/// followed by a blank line.
 ///
-/// * `column_names` - A slice of strings representing the column names.
-/// * `rows` - A slice of Vec<String> representing the rows of data.
-/// * `header_color` - An optional string specifying the color of the header text.
-/// * `cell_color` - An optional string specifying the color of the cell text.
-/// * `writer` - A mutable reference to a writer implementing the `Write` trait.
+/// The index keeps the format, borders and header color of `options` but none
+/// of its column or row settings.
 ///
-fn print_table_to_writer(
-    column_names: &[&str],
-    rows: &[Vec<String>],
-    header_color: Option<&str>,
-    cell_color: Option<&str>,
+fn write_toc(
+    name: &str,
+    counts: &[(String, usize)],
+    options: &TableOptions,
     writer: &mut impl Write,
-) {
-    let mut column_widths: Vec<usize> = column_names.iter().map(|s| s.len()).collect();
+) -> Result<(), TabbsError> {
+    let rows: Vec<Vec<String>> = counts
+        .iter()
+        .map(|(value, count)| vec![value.clone(), count.to_string()])
+        .collect();
+    let toc_options = TableOptions {
+        format: options.format,
+        header_color: options.header_color.clone(),
+        border: options.border,
+        auto_align: true,
+        ..TableOptions::default()
+    };
+    print_table_to_writer(&[name, "rows"], &rows, &toc_options, writer)?;
+    writeln!(writer)?;
+    Ok(())
+}
 
-    for row in rows {
-        for (i, cell) in row.iter().enumerate() {
-            if i < column_widths.len() {
-                column_widths[i] = column_widths[i].max(cell.len());
+/// Render the table again to `handle` each time `reader` produces a new row.
+///
+/// `render` is given the rows kept and the number of rows read in all. When
+/// `terminal` is set the previous table is erased first so that it is redrawn
+/// in place; otherwise each update is printed after the last.
+///
+fn follow_table(
+    reader: Box<dyn Read + '_>,
+    delimiter: char,
+    parse_options: &ParseOptions,
+    limit: Option<usize>,
+    handle: &mut dyn Write,
+    terminal: bool,
+    render: impl Fn(&[Vec<String>], usize, &mut Vec<u8>) -> Result<(), TabbsError>,
+) -> Result<(), TabbsError> {
+    let mut drawn_lines = 0;
+    follow_rows(
+        BufReader::new(reader),
+        delimiter,
+        parse_options,
+        limit,
+        |rows, total| {
+            let mut output = Vec::new();
+            render(rows, total, &mut output)?;
+            if terminal && drawn_lines > 0 {
+                write!(handle, "\x1b[{}F\x1b[J", drawn_lines)?;
             }
+            handle.write_all(&output)?;
+            handle.flush()?;
+            drawn_lines = output.iter().filter(|&&byte| byte == b'\n').count();
+            Ok(())
+        },
+    )
+}
+
+#[cfg(feature = "clipboard")]
+fn copy_to_clipboard(text: &str) -> Result<(), TabbsError> {
+    Ok(tabbs::clipboard::copy(text)?)
+}
+
+#[cfg(not(feature = "clipboard"))]
+fn copy_to_clipboard(_text: &str) -> Result<(), TabbsError> {
+    Err(TabbsError::Config(
+        "cannot copy to the clipboard: tabbs was built without the `clipboard` feature".to_string(),
+    ))
+}
+
+/// Render each group into its own file in `dir`, named after the group value.
+///
+/// Returns the paths of the files written.
+///
+fn write_groups(
+    dir: &Path,
+    column_names: &[String],
+    groups: &[(String, Vec<Vec<String>>)],
+    options: &TableOptions,
+) -> Result<Vec<PathBuf>, TabbsError> {
+    let column_names: Vec<&str> = column_names.iter().map(|s| s.as_str()).collect();
+    let extension = match options.format {
+        Format::Latex => "tex",
+        Format::Json => "json",
+        Format::AlignedTsv => "tsv",
+        Format::Table | Format::Plain | Format::Jira => "txt",
+    };
+    fs::create_dir_all(dir)
+        .map_err(|err| io_context(err.into(), &format!("Failed to create {}", dir.display())))?;
+    let mut paths: Vec<PathBuf> = Vec::new();
+    for (value, rows) in groups {
+        let stem = group_file_stem(value);
+        let mut path = dir.join(format!("{}.{}", stem, extension));
+        let mut suffix = 2;
+        while paths.contains(&path) {
+            path = dir.join(format!("{}-{}.{}", stem, suffix, extension));
+            suffix += 1;
         }
+        let mut file = File::create(&path).map_err(|err| {
+            io_context(err.into(), &format!("Failed to create {}", path.display()))
+        })?;
+        print_table_to_writer(&column_names, rows, options, &mut file)?;
+        paths.push(path);
     }
+    Ok(paths)
+}
 
-    let separator: String = column_widths
-        .iter()
-        .map(|width| "-".repeat(width + 2))
-        .collect::<Vec<String>>()
-        .join("+");
-
-    writeln!(writer, "+{}+", separator).unwrap();
-    write!(writer, "|").unwrap();
-    for (i, column_name) in column_names.iter().enumerate() {
-        let colored_column_name = header_color.map_or(column_name.to_string(), |color| {
-            column_name.color(color).to_string()
-        });
-        write!(
-            writer,
-            " {:<width$} |",
-            colored_column_name,
-            width = column_widths[i]
-        )
-        .unwrap();
+/// Turn a group value into a file name stem safe on every platform.
+///
+/// Characters other than letters, digits, `-` and `_` become `_`, and an empty
+/// value becomes `empty`.
+///
+fn group_file_stem(value: &str) -> String {
+    if value.is_empty() {
+        return "empty".to_string();
     }
-    writeln!(writer).unwrap();
-    writeln!(writer, "+{}+", separator).unwrap();
+    value
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
 
-    for row in rows {
-        write!(writer, "|").unwrap();
-        for (i, cell) in row.iter().enumerate() {
-            if i < column_widths.len() {
-                let colored_cell =
-                    cell_color.map_or(cell.to_string(), |color| cell.color(color).to_string());
-                write!(
-                    writer,
-                    " {:<width$} |",
-                    colored_cell,
-                    width = column_widths[i]
-                )
-                .unwrap();
+/// Declare each column in `names` as text, so that it is never aligned as a number.
+fn mark_text_columns(
+    column_names: &[String],
+    column_types: &mut [Option<ColumnType>],
+    names: &[&str],
+) -> Result<(), TabbsError> {
+    for name in names {
+        let index = column_names
+            .iter()
+            .position(|column| column == name)
+            .ok_or_else(|| TabbsError::Config(format!("Unknown column: {}", name)))?;
+        column_types[index] = Some(ColumnType::Text);
+    }
+    Ok(())
+}
+
+/// Build a table of each column's name and type.
+///
+/// Declared types take precedence over the type inferred from the first rows.
+///
+fn schema_table(
+    column_names: &[String],
+    rows: &[Vec<String>],
+    declared: &[Option<ColumnType>],
+) -> ([&'static str; 2], Vec<Vec<String>>) {
+    let sample = &rows[..rows.len().min(SCHEMA_SAMPLE_ROWS)];
+    let types = detect_column_types(column_names.len(), sample);
+    let schema_rows = column_names
+        .iter()
+        .zip(types)
+        .enumerate()
+        .map(|(i, (name, detected))| {
+            let kind = declared.get(i).copied().flatten().unwrap_or(detected);
+            vec![name.clone(), kind.to_string()]
+        })
+        .collect();
+    (["column", "type"], schema_rows)
+}
+
+/// A column of the `-c` specification with its optional inline settings.
+#[derive(Debug, Clone, PartialEq)]
+struct ColumnSpec {
+    /// The header text of the column.
+    name: String,
+    /// The alignment given by a `:l`, `:r` or `:c` suffix.
+    align: Option<Align>,
+    /// The type declared by a `::int`, `::float`, `::bool` or `::str` suffix.
+    kind: Option<ColumnType>,
+}
+
+/// Parse an entry of the `-c` specification such as `age::int:r`.
+///
+/// A literal `\n` in the name starts a new header line. Unknown `::type`
+/// suffixes are an error.
+///
+fn parse_column_spec(entry: &str) -> Result<ColumnSpec, TabbsError> {
+    let entry = entry.replace("\\n", "\n");
+    let mut name = entry.as_str();
+    let mut align = None;
+    if let Some((rest, suffix)) = name.rsplit_once(':') {
+        if suffix.len() == 1 && !rest.ends_with(':') {
+            if let Ok(parsed) = suffix.parse::<Align>() {
+                align = Some(parsed);
+                name = rest;
             }
         }
-        writeln!(writer).unwrap();
     }
+    let mut kind = None;
+    if let Some((rest, type_name)) = name.rsplit_once("::") {
+        kind = Some(type_name.parse::<ColumnType>()?);
+        name = rest;
+    }
+    Ok(ColumnSpec {
+        name: name.to_string(),
+        align,
+        kind,
+    })
+}
+
+/// Apply the transforms that rewrite cells in place, such as `--mask` and `--ditto`.
+///
+/// They only look at the rows they are given, so `--follow` applies them again
+/// to the rows of every update.
+///
+fn transform_cells(
+    args: &Args,
+    column_names: &[String],
+    rows: &mut [Vec<String>],
+) -> Result<(), TabbsError> {
+    if let Some(names) = args.value("--mask") {
+        let mask_char = args
+            .value("--mask-char")
+            .map_or(Ok('*'), |value| parse_char(value, "mask character"))?;
+        let names: Vec<&str> = names.split(',').collect();
+        let mask = mask_char.to_string().repeat(MASK_LENGTH);
+        mask_columns(column_names, rows, &names, &mask)?;
+    }
+    if let Some(names) = args.value("--ditto") {
+        let names: Vec<&str> = names.split(',').collect();
+        let mark = args.value("--ditto-mark").unwrap_or("\"");
+        ditto_columns(column_names, rows, &names, mark)?;
+    }
+    for spec in args.values("--expand-column") {
+        let (name, separator) = spec.split_once(':').ok_or_else(|| {
+            TabbsError::Config(format!("Invalid --expand-column value: {}", spec))
+        })?;
+        let separator = parse_char(separator, "expand-column separator")?;
+        expand_column(column_names, rows, name, separator)?;
+    }
+    for spec in args.values("--flatten") {
+        let (name, separator) = spec
+            .split_once(':')
+            .ok_or_else(|| TabbsError::Config(format!("Invalid --flatten value: {}", spec)))?;
+        let separator = parse_char(separator, "flatten separator")?;
+        flatten_column(column_names, rows, name, separator)?;
+    }
+    Ok(())
+}
+
+/// Parse a `COLUMN:TEXT` value of the `option` option into the column and its text.
+fn parse_affix(spec: &str, option: &str) -> Result<(String, String), TabbsError> {
+    spec.split_once(':')
+        .filter(|(column, _)| !column.is_empty())
+        .map(|(column, text)| (column.to_string(), text.to_string()))
+        .ok_or_else(|| TabbsError::Config(format!("Invalid {} value: {}", option, spec)))
+}
+
+/// Parse a single-character argument named `what`, accepting the escapes `\t`, `\n` and `\0`.
+fn parse_char(value: &str, what: &str) -> Result<char, TabbsError> {
+    match value {
+        "\\t" => return Ok('\t'),
+        "\\n" => return Ok('\n'),
+        "\\0" => return Ok('\0'),
+        _ => {}
+    }
+    let mut chars = value.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => Ok(c),
+        _ => Err(TabbsError::Config(format!("Invalid {}: {}", what, value))),
+    }
+}
+
+/// Return a seed for `--sample` that differs between runs.
+fn random_seed() -> u64 {
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_nanos() as u64);
+    nanos ^ u64::from(process::id()).rotate_left(32)
+}
+
+/// Return the effective terminal width, taken from `COLUMNS` when it is set.
+fn terminal_width() -> usize {
+    env::var("COLUMNS")
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(DEFAULT_TERMINAL_WIDTH)
+}
+
+/// Return whether the locale named by `LC_ALL`, `LC_CTYPE` or `LANG` uses UTF-8.
+///
+/// The first of these that is set and non-empty decides, as in the C library; with
+/// none set the locale is `C`, which is not UTF-8.
+///
+fn locale_is_utf8(var: impl Fn(&str) -> Option<String>) -> bool {
+    ["LC_ALL", "LC_CTYPE", "LANG"]
+        .iter()
+        .filter_map(|name| var(name))
+        .find(|value| !value.is_empty())
+        .is_some_and(|value| {
+            let value = value.to_lowercase();
+            value.contains("utf-8") || value.contains("utf8")
+        })
+}
+
+/// Return whether to emit colors, following the `NO_COLOR` and `CLICOLOR` conventions.
+///
+/// A `CLICOLOR_FORCE` other than `0` turns colors on even when stdout is not a
+/// terminal. Otherwise a non-empty `NO_COLOR` or `CLICOLOR=0` turns them off, and
+/// they are used only on a terminal.
+///
+fn color_enabled(var: impl Fn(&str) -> Option<String>, terminal: bool) -> bool {
+    let set = |name: &str| var(name).filter(|value| !value.is_empty());
+    if set("CLICOLOR_FORCE").is_some_and(|value| value != "0") {
+        return true;
+    }
+    if set("NO_COLOR").is_some() || set("CLICOLOR").is_some_and(|value| value == "0") {
+        return false;
+    }
+    terminal
+}
+
+/// Return `border`, or ASCII borders if the locale cannot display it and
+/// `force_unicode` is not set.
+fn border_for_locale(border: BorderChars, utf8: bool, force_unicode: bool) -> BorderChars {
+    if border.is_ascii() || utf8 || force_unicode {
+        border
+    } else {
+        BorderChars::ASCII
+    }
+}
+
+/// Read the column entries of a `--columns-file`.
+///
+/// The file lists one column per line, or all of them on a single line separated by
+/// commas. Blank lines are ignored, and each entry may carry the same `::type` and
+/// `:l|r|c` suffixes as `-c`.
+///
+fn read_columns_file(path: &str) -> Result<Vec<String>, TabbsError> {
+    let text = fs::read_to_string(path)
+        .map_err(|err| io_context(err.into(), &format!("Failed to read {}", path)))?;
+    let lines: Vec<&str> = text
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect();
+    let entries: Vec<String> = match lines.as_slice() {
+        [line] => line
+            .split(',')
+            .map(|entry| entry.trim().to_string())
+            .collect(),
+        _ => lines.iter().map(|line| line.to_string()).collect(),
+    };
+    if entries.is_empty() {
+        return Err(TabbsError::Config(format!("No columns in {}", path)));
+    }
+    Ok(entries)
+}
+
+/// Parse a `--lines` range such as `100-200`, `100-` or `-200` into its first and last line.
+fn parse_line_range(spec: &str) -> Result<(usize, Option<usize>), TabbsError> {
+    let invalid = || TabbsError::Config(format!("Invalid line range: {}", spec));
+    let (first, last) = spec.split_once('-').ok_or_else(invalid)?;
+    let first = match first.trim() {
+        "" => 1,
+        first => first.parse().map_err(|_| invalid())?,
+    };
+    let last = match last.trim() {
+        "" => None,
+        last => Some(last.parse().map_err(|_| invalid())?),
+    };
+    if first == 0 || last.is_some_and(|last| last < first) {
+        return Err(invalid());
+    }
+    Ok((first, last))
+}
+
+/// Parse a `--fixed-widths` specification such as `10,5,20` into field widths.
+fn parse_fixed_widths(spec: &str) -> Result<Vec<usize>, TabbsError> {
+    spec.split(',')
+        .map(|entry| match entry.trim().parse() {
+            Ok(width) if width > 0 => Ok(width),
+            _ => Err(TabbsError::Config(format!(
+                "Invalid fixed width: {}",
+                entry
+            ))),
+        })
+        .collect()
+}
+
+/// A single entry of a `--col-widths` specification.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ColumnWidth {
+    /// An absolute width in display columns.
+    Absolute(usize),
+    /// A percentage of the width available for cell content.
+    Percent(f64),
+    /// No explicit width; the column is sized from its content.
+    Auto,
+}
 
-    writeln!(writer, "+{}+", separator).unwrap();
+/// Parse a comma-separated width specification such as `20,25%,,10`.
+///
+/// Empty entries leave the corresponding column sized from its content.
+///
+fn parse_col_widths(spec: &str) -> Result<Vec<ColumnWidth>, TabbsError> {
+    spec.split(',')
+        .map(|entry| {
+            let entry = entry.trim();
+            if entry.is_empty() {
+                Ok(ColumnWidth::Auto)
+            } else if let Some(percent) = entry.strip_suffix('%') {
+                match percent.trim().parse::<f64>() {
+                    Ok(value) if (0.0..=100.0).contains(&value) => Ok(ColumnWidth::Percent(value)),
+                    _ => Err(TabbsError::Config(format!(
+                        "Invalid percentage width: {}",
+                        entry
+                    ))),
+                }
+            } else {
+                entry
+                    .parse()
+                    .map(ColumnWidth::Absolute)
+                    .map_err(|_| TabbsError::Config(format!("Invalid column width: {}", entry)))
+            }
+        })
+        .collect()
+}
+
+/// Resolve width specifications into absolute widths for `column_count` columns.
+///
+/// Percentages are taken of `total_width` minus the space used by borders and
+/// cell padding, so `50%,25%,25%` fills the whole table width. Each percentage
+/// column ends where the running total of the percentages rounds to, which
+/// spreads the rounding over the columns instead of leaving the table short.
+///
+fn resolve_col_widths(
+    specs: &[ColumnWidth],
+    total_width: usize,
+    column_count: usize,
+) -> Vec<Option<usize>> {
+    let available = total_width.saturating_sub(3 * column_count + 1);
+    let (mut share, mut used) = (0.0, 0);
+    (0..column_count)
+        .map(|i| match specs.get(i) {
+            Some(ColumnWidth::Absolute(width)) => Some(*width),
+            Some(ColumnWidth::Percent(percent)) => {
+                share += (available as f64) * percent / 100.0;
+                let end = (share.round() as usize).max(used);
+                let width = end - used;
+                used = end;
+                Some(width)
+            }
+            Some(ColumnWidth::Auto) | None => None,
+        })
+        .collect()
 }
 
 #[cfg(test)]
@@ -147,29 +1507,528 @@ mod tests {
     use super::*;
 
     #[test]
-    fn test_print_table_to_writer() {
-        let column_names = ["name", "age", "text"];
-        let rows = [
+    fn test_percentage_column_widths() {
+        let specs = parse_col_widths("50%,25%,25%").unwrap();
+        assert_eq!(
+            specs,
+            vec![
+                ColumnWidth::Percent(50.0),
+                ColumnWidth::Percent(25.0),
+                ColumnWidth::Percent(25.0)
+            ]
+        );
+        // Three columns spend 10 characters on borders and padding, leaving 30.
+        let widths = resolve_col_widths(&specs, 40, 3);
+        assert_eq!(widths, vec![Some(15), Some(8), Some(7)]);
+        assert_eq!(widths.iter().flatten().sum::<usize>(), 30);
+        let thirds = parse_col_widths("33.33%,33.33%,33.34%").unwrap();
+        assert_eq!(
+            resolve_col_widths(&thirds, 41, 3),
+            vec![Some(10), Some(11), Some(10)]
+        );
+
+        let mixed = parse_col_widths("4,50%,").unwrap();
+        assert_eq!(
+            resolve_col_widths(&mixed, 40, 3),
+            vec![Some(4), Some(15), None]
+        );
+
+        let column_names = ["name", "text"];
+        let rows = [vec!["jack".to_string(), "a rather long note".to_string()]];
+        let options = TableOptions {
+            column_widths: resolve_col_widths(&parse_col_widths("20%,80%").unwrap(), 27, 2),
+            ..TableOptions::default()
+        };
+        let mut output = Vec::new();
+        print_table_to_writer(&column_names, &rows, &options, &mut output).unwrap();
+        let expected_output = "\
++------+------------------+
+| name | text             |
++------+------------------+
+| jack | a rather long n… |
++------+------------------+
+";
+        assert_eq!(String::from_utf8(output).unwrap(), expected_output);
+    }
+
+    #[test]
+    fn test_inline_column_alignment() {
+        let specs: Vec<ColumnSpec> = "name,age:r,pct:c,ratio:x,a:left"
+            .split(',')
+            .map(|entry| parse_column_spec(entry).unwrap())
+            .collect();
+        let parsed: Vec<(&str, Option<Align>)> = specs
+            .iter()
+            .map(|spec| (spec.name.as_str(), spec.align))
+            .collect();
+        assert_eq!(
+            parsed,
+            vec![
+                ("name", None),
+                ("age", Some(Align::Right)),
+                ("pct", Some(Align::Center)),
+                ("ratio:x", None),
+                ("a:left", None),
+            ]
+        );
+        assert_eq!(
+            parse_column_spec("first\\nname:r").unwrap().name,
+            "first\nname"
+        );
+    }
+
+    #[test]
+    fn test_schema_table() {
+        let column_names = vec!["name".to_string(), "age".to_string(), "text".to_string()];
+        let rows = vec![
             vec!["jack".to_string(), "35".to_string(), "neat".to_string()],
             vec!["jane".to_string(), "50".to_string(), "cool".to_string()],
             vec!["erin".to_string(), "20".to_string(), "nice".to_string()],
         ];
-
+        let (schema_columns, schema_rows) = schema_table(&column_names, &rows, &[]);
+        let mut output = Vec::new();
+        print_table_to_writer(
+            &schema_columns,
+            &schema_rows,
+            &TableOptions::default(),
+            &mut output,
+        )
+        .unwrap();
         let expected_output = "\
-+------+-----+------+
-| name | age | text |
-+------+-----+------+
-| jack | 35  | neat |
-| jane | 50  | cool |
-| erin | 20  | nice |
-+------+-----+------+";
++--------+------+
+| column | type |
++--------+------+
+| name   | str  |
+| age    | int  |
+| text   | str  |
++--------+------+
+";
+        assert_eq!(String::from_utf8(output).unwrap(), expected_output);
+    }
+
+    #[test]
+    fn test_inline_type_hints() {
+        let spec = parse_column_spec("id::str:r").unwrap();
+        assert_eq!(
+            (spec.name.as_str(), spec.align, spec.kind),
+            ("id", Some(Align::Right), Some(ColumnType::Text))
+        );
+        let spec = parse_column_spec("price::float").unwrap();
+        assert_eq!(
+            (spec.name.as_str(), spec.kind),
+            ("price", Some(ColumnType::Float))
+        );
+        assert!(parse_column_spec("age::number").is_err());
+
+        let column_names = vec!["id".to_string(), "qty".to_string()];
+        let rows = vec![
+            vec!["7".to_string(), "5".to_string()],
+            vec!["1042".to_string(), "12".to_string()],
+        ];
+        let declared = [Some(ColumnType::Text), None];
+        let (_, schema_rows) = schema_table(&column_names, &rows, &declared);
+        assert_eq!(schema_rows, vec![vec!["id", "str"], vec!["qty", "int"]]);
+
+        let options = TableOptions {
+            auto_align: true,
+            column_types: declared.to_vec(),
+            ..TableOptions::default()
+        };
+        let mut output = Vec::new();
+        print_table_to_writer(&["id", "qty"], &rows, &options, &mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("| 7    |   5 |"));
+        assert!(output.contains("| 1042 |  12 |"));
+    }
+
+    #[test]
+    fn test_parse_fixed_widths() {
+        assert_eq!(parse_fixed_widths("10,5,20").unwrap(), vec![10, 5, 20]);
+        assert!(parse_fixed_widths("10,,20").is_err());
+        assert!(parse_fixed_widths("0").is_err());
+    }
+
+    #[test]
+    fn test_border_for_locale() {
+        let locale = |vars: &'static [(&'static str, &'static str)]| {
+            move |name: &str| {
+                vars.iter()
+                    .find(|(var, _)| *var == name)
+                    .map(|(_, value)| value.to_string())
+            }
+        };
+        assert!(locale_is_utf8(locale(&[("LANG", "en_US.UTF-8")])));
+        assert!(locale_is_utf8(locale(&[
+            ("LC_ALL", "C.utf8"),
+            ("LANG", "C")
+        ])));
+        assert!(!locale_is_utf8(locale(&[
+            ("LC_ALL", "C"),
+            ("LANG", "en_US.UTF-8")
+        ])));
+        assert!(!locale_is_utf8(locale(&[("LANG", "en_US.ISO-8859-1")])));
+        assert!(!locale_is_utf8(locale(&[])));
+
+        let utf8 = locale_is_utf8(locale(&[("LANG", "POSIX")]));
+        assert_eq!(
+            border_for_locale(BorderChars::UNICODE, utf8, false),
+            BorderChars::ASCII
+        );
+        assert_eq!(
+            border_for_locale(BorderChars::UNICODE, utf8, true),
+            BorderChars::UNICODE
+        );
+        assert_eq!(
+            border_for_locale(BorderChars::UNICODE, true, false),
+            BorderChars::UNICODE
+        );
+    }
+
+    #[test]
+    fn test_text_columns() {
+        let column_names = vec!["id".to_string(), "qty".to_string()];
+        let mut column_types = vec![None, None];
+        mark_text_columns(&column_names, &mut column_types, &["id"]).unwrap();
+        assert_eq!(column_types, vec![Some(ColumnType::Text), None]);
+        assert!(mark_text_columns(&column_names, &mut column_types, &["sku"]).is_err());
+
+        let rows = vec![
+            vec!["7".to_string(), "5".to_string()],
+            vec!["1042".to_string(), "12".to_string()],
+        ];
+        let options = TableOptions {
+            auto_align: true,
+            column_types,
+            ..TableOptions::default()
+        };
         let mut output = Vec::new();
+        print_table_to_writer(&["id", "qty"], &rows, &options, &mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("| 7    |   5 |"));
+    }
+
+    #[test]
+    fn test_write_groups() {
+        let dir = env::temp_dir().join(format!("tabbs-split-{}", process::id()));
+        let column_names = vec!["item".to_string(), "kind".to_string()];
+        let rows = vec![
+            vec!["tea".to_string(), "hot drink".to_string()],
+            vec!["cake".to_string(), "food/sweet".to_string()],
+            vec!["coffee".to_string(), "hot drink".to_string()],
+        ];
+        let groups = group_rows(&rows, 1);
+        let paths = write_groups(&dir, &column_names, &groups, &TableOptions::default()).unwrap();
+        assert_eq!(
+            paths,
+            vec![dir.join("hot_drink.txt"), dir.join("food_sweet.txt")]
+        );
+        let drinks = fs::read_to_string(&paths[0]).unwrap();
+        assert!(drinks.contains("| tea    | hot drink |"));
+        assert!(drinks.contains("| coffee | hot drink |"));
+        assert!(!drinks.contains("cake"));
+        fs::remove_dir_all(dir).unwrap();
+
+        assert_eq!(group_file_stem("../etc"), "___etc");
+        assert_eq!(group_file_stem(""), "empty");
+    }
+
+    #[test]
+    fn test_read_columns_file() {
+        let path = env::temp_dir().join(format!("tabbs-columns-{}", process::id()));
+        let path_str = path.to_str().unwrap();
+        fs::write(&path, "name\nage::int:r\n\ntext, notes\n").unwrap();
+        assert_eq!(
+            read_columns_file(path_str).unwrap(),
+            vec!["name", "age::int:r", "text, notes"]
+        );
+        fs::write(&path, "name, age,text\n").unwrap();
+        assert_eq!(
+            read_columns_file(path_str).unwrap(),
+            vec!["name", "age", "text"]
+        );
+        fs::write(&path, "\n").unwrap();
+        assert!(read_columns_file(path_str).is_err());
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_reporter_verbosity() {
+        let note = delimiter_note(';', true);
+        assert_eq!(note, "splitting fields on ';' (--delimiter)");
 
-        {
-            let mut output_writer = std::io::BufWriter::new(output.by_ref());
-            print_table_to_writer(&column_names, &rows, None, None, &mut output_writer);
+        let mut reporter = Reporter::new(Verbosity::Verbose, Vec::new());
+        reporter.note(&note);
+        reporter.warn("Repaired 1 of 2 rows to 3 fields");
+        assert_eq!(
+            String::from_utf8(reporter.sink).unwrap(),
+            "note: splitting fields on ';' (--delimiter)\nRepaired 1 of 2 rows to 3 fields\n"
+        );
+
+        let mut reporter = Reporter::new(Verbosity::Normal, Vec::new());
+        reporter.note(&delimiter_note('\t', false));
+        reporter.warn("Repaired 1 of 2 rows to 3 fields");
+        assert_eq!(
+            String::from_utf8(reporter.sink).unwrap(),
+            "Repaired 1 of 2 rows to 3 fields\n"
+        );
+
+        let mut reporter = Reporter::new(Verbosity::Quiet, Vec::new());
+        reporter.warn("Repaired 1 of 2 rows to 3 fields");
+        assert!(reporter.sink.is_empty());
+    }
+
+    #[test]
+    fn test_parse_line_range() {
+        assert_eq!(parse_line_range("100-200").unwrap(), (100, Some(200)));
+        assert_eq!(parse_line_range("5-").unwrap(), (5, None));
+        assert_eq!(parse_line_range("-3").unwrap(), (1, Some(3)));
+        assert!(parse_line_range("0-3").is_err());
+        assert!(parse_line_range("4-2").is_err());
+        assert!(parse_line_range("7").is_err());
+    }
+
+    #[test]
+    fn test_color_enabled() {
+        let env = |vars: &'static [(&'static str, &'static str)]| {
+            move |name: &str| {
+                vars.iter()
+                    .find(|(var, _)| *var == name)
+                    .map(|(_, value)| value.to_string())
+            }
+        };
+        assert!(color_enabled(env(&[]), true));
+        assert!(!color_enabled(env(&[]), false));
+        assert!(!color_enabled(env(&[("CLICOLOR", "0")]), true));
+        assert!(color_enabled(env(&[("CLICOLOR", "1")]), true));
+        assert!(!color_enabled(env(&[("NO_COLOR", "1")]), true));
+        assert!(color_enabled(env(&[("NO_COLOR", "")]), true));
+        assert!(color_enabled(env(&[("CLICOLOR_FORCE", "1")]), false));
+        assert!(color_enabled(
+            env(&[("CLICOLOR_FORCE", "1"), ("NO_COLOR", "1")]),
+            false
+        ));
+        assert!(!color_enabled(env(&[("CLICOLOR_FORCE", "0")]), false));
+    }
+
+    #[test]
+    fn test_write_toc() {
+        let column_names = vec!["team".to_string(), "name".to_string()];
+        let mut rows = vec![
+            vec!["red".to_string(), "jack".to_string()],
+            vec!["blue".to_string(), "erin".to_string()],
+            vec!["red".to_string(), "jane".to_string()],
+        ];
+        group_by(&column_names, &mut rows, "team").unwrap();
+        let options = TableOptions::default();
+        let mut output = Vec::new();
+        write_toc("team", &group_counts(&rows, 0), &options, &mut output).unwrap();
+        print_table_to_writer(&["team", "name"], &rows, &options, &mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.starts_with(
+            "+------+------+\n\
+             | team | rows |\n\
+             +------+------+\n\
+             | red  |    2 |\n\
+             | blue |    1 |\n\
+             +------+------+\n\
+             \n\
+             +------+------+\n\
+             | team | name |\n"
+        ));
+        assert!(output.ends_with("| blue | erin |\n+------+------+\n"));
+    }
+
+    /// Run tabbs on `input` and return its exit code, stdout and stderr.
+    fn run_with(args: &[&str], input: &'static str) -> (i32, String, String) {
+        let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+        let (mut stdout, mut stderr) = (Vec::new(), Vec::new());
+        let code = run(
+            &args,
+            input.as_bytes(),
+            &mut stdout,
+            &mut stderr,
+            Terminals::default(),
+            PROGRESS_INTERVAL,
+        );
+        (
+            code,
+            String::from_utf8(stdout).unwrap(),
+            String::from_utf8(stderr).unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_run_exit_codes() {
+        let (code, stdout, stderr) = run_with(&["-c", "a,b"], "1,2\n");
+        assert_eq!(code, 0);
+        assert!(stdout.contains("| 1 | 2 |"));
+        assert!(stderr.is_empty());
+
+        let (code, stdout, stderr) = run_with(&[], "");
+        assert_eq!(code, 64);
+        assert!(stdout.is_empty());
+        assert!(stderr.contains(cli::USAGE));
+
+        let (code, _, stderr) = run_with(&["-c", "a", "--format", "html"], "");
+        assert_eq!(code, 64);
+        assert_eq!(stderr, "Unknown output format: html\n");
+
+        let (code, _, stderr) = run_with(&["-c", "a,b", "--strict"], "1,2\n3\n");
+        assert_eq!(code, 65);
+        assert!(stderr.contains("line 2"));
+
+        let mut closed = io::Cursor::new([0u8; 0]);
+        let mut stderr = Vec::new();
+        let code = run(
+            &["--help".to_string()],
+            io::empty(),
+            &mut closed,
+            &mut stderr,
+            Terminals::default(),
+            PROGRESS_INTERVAL,
+        );
+        assert_eq!(code, 74);
+        assert!(!stderr.is_empty());
+    }
+
+    #[test]
+    fn test_run_streams() {
+        let path = env::temp_dir().join(format!("tabbs-run-streams-{}", process::id()));
+        fs::write(&path, "1,2\n".repeat(1000)).unwrap();
+        let args: Vec<String> = ["-c", "a,b", "-f", path.to_str().unwrap(), "--limit", "1"]
+            .iter()
+            .map(|arg| arg.to_string())
+            .collect();
+        let (mut stdout, mut stderr) = (Vec::new(), Vec::new());
+        let terminals = Terminals {
+            stdout: false,
+            stderr: true,
+        };
+        assert_eq!(
+            run(
+                &args,
+                io::empty(),
+                &mut stdout,
+                &mut stderr,
+                terminals,
+                1024
+            ),
+            0
+        );
+        fs::remove_file(path).unwrap();
+        assert!(String::from_utf8(stdout).unwrap().contains("| 1 | 2 |"));
+        assert!(String::from_utf8(stderr)
+            .unwrap()
+            .ends_with("\rRead    3.9 KiB\n"));
+    }
+
+    #[test]
+    fn test_run_verbose_note() {
+        let (code, _, stderr) = run_with(&["-c", "a", "--verbose"], "1\n");
+        assert_eq!(code, 0);
+        assert!(stderr.starts_with("note: "));
+    }
+
+    #[test]
+    fn test_follow_transforms() {
+        let input = "jack,a@x.io,3,a|b,true\njack,b@x.io,1,c,false\n,,,,\njane,c@x.io,2,,true\n";
+        let meta = env::temp_dir().join(format!("tabbs-follow-meta-{}", process::id()));
+        let meta = meta.to_str().unwrap();
+        let transforms: &[&[&str]] = &[
+            &["--input", "kv"],
+            &["--lines", "2:3"],
+            &["--fixed-widths", "4,8"],
+            &["--record-sep", ";"],
+            &["--stdin0"],
+            &["--self-describe"],
+            &["--diff", "old.csv"],
+            &["--repair"],
+            &["--drop-empty-rows"],
+            &["--compute", "m = n * 2"],
+            &["--columns", "name,n"],
+            &["--sort-columns"],
+            &["--strict"],
+            &["--histogram", "name"],
+            &["--sort", "n"],
+            &["--group-by", "name", "--group-lines"],
+            &["--toc"],
+            &["--sample", "2", "--seed", "1"],
+            &["--cumulative", "n"],
+            &["--mask", "email"],
+            &["--ditto", "name"],
+            &["--ditto", "name", "--ditto-mark", ""],
+            &["--expand-column", "tags:|"],
+            &["--flatten", "tags:|"],
+            &["--number"],
+            &["--number", "--orig-index"],
+            &["--drop-empty-cols"],
+            &["--rotate"],
+            &["--abbrev-numbers"],
+            &["--round", "1"],
+            &["--dim", "n>1"],
+            &["--bool-symbols"],
+            &["--bool-symbols", "--true-symbol", "y", "--number"],
+            &["--meta-out", meta],
+            &["--schema"],
+            &["--split-by", "name", "--out-dir", "groups"],
+            &["--clipboard"],
+        ];
+        for transform in transforms {
+            let mut args = vec!["-c", "name,email,n,tags,ok", "--follow"];
+            args.extend_from_slice(transform);
+            let (code, followed, stderr) = run_with(&args, input);
+            if code != 0 {
+                assert_eq!(code, 64, "{:?}: {}", transform, stderr);
+                assert!(stderr.starts_with("--follow cannot be combined with"));
+                continue;
+            }
+            args.remove(2);
+            let (code, table, stderr) = run_with(&args, input);
+            assert_eq!(code, 0, "{:?}: {}", transform, stderr);
+            assert!(followed.ends_with(&table), "{:?}:\n{}", transform, followed);
         }
-        let output_str = String::from_utf8(output).unwrap();
-        assert_eq!(output_str.trim(), expected_output);
+        let _ = fs::remove_file(meta);
+    }
+
+    #[test]
+    fn test_abbrev_numbers_columns() {
+        let input: String = (1..=1001).map(|n| format!("{}\n", n)).collect();
+        let (code, stdout, _) =
+            run_with(&["-c", "n", "--number", "--abbrev-numbers"], input.leak());
+        assert_eq!(code, 0);
+        assert!(stdout.ends_with("| 1000 | 1K  |\n| 1001 | 1K  |\n+------+-----+\n"));
+
+        let (code, stdout, _) =
+            run_with(&["-c", "id::str,n", "--abbrev-numbers"], "123456,123456\n");
+        assert_eq!(code, 0);
+        assert!(stdout.contains("| 123456 | 123.5K |"), "{}", stdout);
+    }
+
+    #[test]
+    fn test_bool_symbols_row_numbers() {
+        let (code, stdout, _) = run_with(&["-c", "ok,n", "--number", "--bool-symbols"], "yes,1\n");
+        assert_eq!(code, 0);
+        assert!(stdout.contains("| 1 | ✓  | 1 |"), "{}", stdout);
+    }
+
+    #[test]
+    fn test_lines_self_describe() {
+        let input = "sep=;\nname;age\njack;1\njane;2\nerin;3\n";
+        let args = ["--self-describe", "--lines", "4-4", "--format", "plain"];
+        let (code, stdout, stderr) = run_with(&args, input);
+        assert_eq!(code, 0, "{}", stderr);
+        assert_eq!(stdout, "name  age\njane    2\n");
+    }
+
+    #[test]
+    fn test_follow_number_limit() {
+        let args = ["-c", "n", "--follow", "--number", "--limit", "2"];
+        let (code, stdout, _) = run_with(&args, "a\nb\nc\n");
+        assert_eq!(code, 0);
+        assert!(
+            stdout.ends_with("| 2 | b |\n| 3 | c |\n+---+---+\n"),
+            "{}",
+            stdout
+        );
     }
 }
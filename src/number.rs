@@ -0,0 +1,223 @@
+//! Per-column formatting of numeric cells with `--num-format`.
+
+use crate::error::TabbsError;
+use std::str::FromStr;
+
+/// The digit grouping and decimal separators of a locale, such as `1.234,56` in German.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NumberLocale {
+    /// The separator between groups of three integer digits.
+    pub grouping: char,
+    /// The separator between the integer and fractional digits.
+    pub decimal: char,
+}
+
+impl NumberLocale {
+    /// Rewrite a plain number such as `-1234.5` with the locale's separators.
+    ///
+    /// Returns `None` when `number` is not written as optional sign, digits and an
+    /// optional fraction, so exponents and formatted text are left alone.
+    ///
+    pub fn localize(&self, number: &str) -> Option<String> {
+        let number = number.trim();
+        let (sign, digits) = match number.strip_prefix(['-', '+']) {
+            Some(rest) => (&number[..1], rest),
+            None => ("", number),
+        };
+        let (integer, fraction) = match digits.split_once('.') {
+            Some((integer, fraction)) => (integer, Some(fraction)),
+            None => (digits, None),
+        };
+        let all_digits = |s: &str| !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit());
+        if !all_digits(integer) || fraction.is_some_and(|fraction| !all_digits(fraction)) {
+            return None;
+        }
+        let mut localized = sign.to_string();
+        for (i, digit) in integer.chars().enumerate() {
+            if i > 0 && (integer.len() - i) % 3 == 0 {
+                localized.push(self.grouping);
+            }
+            localized.push(digit);
+        }
+        if let Some(fraction) = fraction {
+            localized.push(self.decimal);
+            localized.push_str(fraction);
+        }
+        Some(localized)
+    }
+}
+
+impl FromStr for NumberLocale {
+    type Err = TabbsError;
+
+    /// Parse a locale tag such as `de`, `de-CH` or `fr_FR`, matched by language and region.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let tag = s.replace('_', "-").to_lowercase();
+        let language = tag.split('-').next().unwrap_or_default();
+        let (grouping, decimal) = match (language, tag.as_str()) {
+            (_, "de-ch" | "it-ch") => ('\'', '.'),
+            ("en" | "ja" | "ko" | "zh" | "he" | "th", _) => (',', '.'),
+            ("de" | "es" | "it" | "nl" | "pt" | "da" | "id" | "tr" | "el", _) => ('.', ','),
+            ("fr" | "ru" | "pl" | "sv" | "nb" | "fi" | "cs" | "sk" | "uk", _) => ('\u{a0}', ','),
+            _ => return Err(TabbsError::Config(format!("Unknown locale: {}", s))),
+        };
+        Ok(NumberLocale { grouping, decimal })
+    }
+}
+
+/// A format pattern such as `${:.2}` applied to the numeric cells of a column.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NumberFormat {
+    /// The name of the column the pattern applies to.
+    pub column: String,
+    /// Text written before the number.
+    pub prefix: String,
+    /// The number of decimal places, or `None` to keep the number as written.
+    pub precision: Option<usize>,
+    /// Text written after the number.
+    pub suffix: String,
+}
+
+impl NumberFormat {
+    /// Format `cell` with the pattern, or return `None` if it is not a number.
+    pub fn apply(&self, cell: &str) -> Option<String> {
+        self.apply_in(cell, None)
+    }
+
+    /// Format `cell` with the pattern, writing the number with the separators of `locale`.
+    pub fn apply_in(&self, cell: &str, locale: Option<&NumberLocale>) -> Option<String> {
+        let value = cell.trim();
+        let number: f64 = value.parse().ok().filter(|n: &f64| n.is_finite())?;
+        let number = match self.precision {
+            Some(places) => format!("{:.*}", places, number),
+            None => value.to_string(),
+        };
+        let number = match locale {
+            Some(locale) => locale.localize(&number).unwrap_or(number),
+            None => number,
+        };
+        Some(format!("{}{}{}", self.prefix, number, self.suffix))
+    }
+}
+
+/// Abbreviate `value` with an SI-style suffix, such as `1.5K` for `1500`.
+///
+/// Values below one thousand are returned as `None`. Without `precision` the
+/// mantissa has at most one decimal place and a trailing `.0` is dropped, so
+/// `2000000` becomes `2M`; with `precision` exactly that many places are shown.
+///
+pub fn abbreviate_number(value: f64, precision: Option<usize>) -> Option<String> {
+    const SUFFIXES: [&str; 4] = ["K", "M", "B", "T"];
+    if !value.is_finite() || value.abs() < 1000.0 {
+        return None;
+    }
+    let places = precision.unwrap_or(1);
+    let mut unit = 0;
+    let mut mantissa = value / 1000.0;
+    loop {
+        let rounded = format!("{:.*}", places, mantissa);
+        let carries = rounded.trim_start_matches('-').parse::<f64>().unwrap() >= 1000.0;
+        if carries && unit + 1 < SUFFIXES.len() {
+            mantissa /= 1000.0;
+            unit += 1;
+            continue;
+        }
+        let rounded = match precision {
+            Some(_) => rounded,
+            None if rounded.contains('.') => rounded
+                .trim_end_matches('0')
+                .trim_end_matches('.')
+                .to_string(),
+            None => rounded,
+        };
+        return Some(format!("{}{}", rounded, SUFFIXES[unit]));
+    }
+}
+
+/// Parse a specification such as `price:${:.2},qty:{:}` into one format per column.
+///
+/// Each pattern holds a single `{:}` or `{:.N}` placeholder for the number,
+/// surrounded by literal text.
+///
+pub fn parse_number_formats(spec: &str) -> Result<Vec<NumberFormat>, TabbsError> {
+    spec.split(',')
+        .map(|entry| {
+            let (column, pattern) = entry
+                .split_once(':')
+                .ok_or_else(|| TabbsError::Config(format!("Invalid number format: {}", entry)))?;
+            let invalid =
+                || TabbsError::Config(format!("Invalid number format for {}: {}", column, pattern));
+            let (prefix, rest) = pattern.split_once('{').ok_or_else(invalid)?;
+            let (placeholder, suffix) = rest.split_once('}').ok_or_else(invalid)?;
+            if suffix.contains(['{', '}']) {
+                return Err(invalid());
+            }
+            let precision = match placeholder.strip_prefix(':').ok_or_else(invalid)? {
+                "" => None,
+                places => Some(
+                    places
+                        .strip_prefix('.')
+                        .and_then(|places| places.parse().ok())
+                        .ok_or_else(invalid)?,
+                ),
+            };
+            Ok(NumberFormat {
+                column: column.to_string(),
+                prefix: prefix.to_string(),
+                precision,
+                suffix: suffix.to_string(),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_number_formats() {
+        let formats = parse_number_formats("price:${:.2},qty:{:} pcs").unwrap();
+        assert_eq!(formats[0].apply("3.14159"), Some("$3.14".to_string()));
+        assert_eq!(formats[0].apply("12"), Some("$12.00".to_string()));
+        assert_eq!(formats[0].apply("free"), None);
+        assert_eq!(formats[1].apply("5"), Some("5 pcs".to_string()));
+
+        assert!(parse_number_formats("price").is_err());
+        assert!(parse_number_formats("price:$").is_err());
+        assert!(parse_number_formats("price:{:x}").is_err());
+        assert!(parse_number_formats("price:{:.2}{:}").is_err());
+    }
+
+    #[test]
+    fn test_abbreviate_number() {
+        assert_eq!(abbreviate_number(1500.0, None), Some("1.5K".to_string()));
+        assert_eq!(abbreviate_number(2000000.0, None), Some("2M".to_string()));
+        assert_eq!(abbreviate_number(1000.0, Some(1)), Some("1.0K".to_string()));
+        assert_eq!(
+            abbreviate_number(-3400000000.0, None),
+            Some("-3.4B".to_string())
+        );
+        assert_eq!(abbreviate_number(999960.0, None), Some("1M".to_string()));
+        assert_eq!(abbreviate_number(999.0, None), None);
+    }
+
+    #[test]
+    fn test_number_locale() {
+        let german: NumberLocale = "de-DE".parse().unwrap();
+        assert_eq!(
+            german.localize("1234567.891"),
+            Some("1.234.567,891".to_string())
+        );
+        assert_eq!(german.localize("-999"), Some("-999".to_string()));
+        assert_eq!(german.localize("1e5"), None);
+        let swiss: NumberLocale = "de_CH".parse().unwrap();
+        assert_eq!(swiss.localize("1234.5"), Some("1'234.5".to_string()));
+        let format = &parse_number_formats("price:{:.2} €").unwrap()[0];
+        assert_eq!(
+            format.apply_in("1234.5", Some(&german)),
+            Some("1.234,50 €".to_string())
+        );
+        assert!("xx".parse::<NumberLocale>().is_err());
+    }
+}
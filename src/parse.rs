@@ -0,0 +1,748 @@
+//! Parsing of delimited input into rows of fields.
+
+use crate::error::TabbsError;
+use std::cell::Cell;
+use std::io::{BufRead, BufReader, Read};
+
+/// The optional header row and the data rows produced by `parse_input`.
+pub type ParsedInput = (Option<Vec<String>>, Vec<Vec<String>>);
+
+/// Options controlling how `parse_input` splits input into rows.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseOptions {
+    /// Treat the first record as the header row.
+    pub has_header: bool,
+    /// Skip lines whose first non-blank character is this comment marker.
+    pub comment: Option<char>,
+    /// The character used to quote fields containing delimiters or newlines.
+    pub quote: Option<char>,
+    /// Trim surrounding whitespace from unquoted fields.
+    pub trim: bool,
+    /// Treat `\` followed by the delimiter or another `\` as a literal character in unquoted fields.
+    pub backslash_escape: bool,
+    /// Turn unquoted fields holding only whitespace into empty fields, even without `trim`.
+    pub blank_as_empty: bool,
+    /// The character ending each record. With anything but `\n`, line breaks are part of the fields.
+    pub record_separator: char,
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        ParseOptions {
+            has_header: false,
+            comment: None,
+            quote: Some('"'),
+            trim: true,
+            backslash_escape: false,
+            blank_as_empty: false,
+            record_separator: '\n',
+        }
+    }
+}
+
+/// Parse delimited records from `reader`.
+///
+/// Records are separated by `\n` or `\r\n`, or by `opts.record_separator` when
+/// it is set to another character, and fields by `delimiter`; a space
+/// delimiter splits on runs of spaces and tabs instead. Quoted fields may contain
+/// delimiters, newlines and doubled quotes. Blank lines are skipped. Returns the
+/// header row, when `opts.has_header` is set, along with the remaining rows.
+///
+/// # Arguments
+///
+/// * `reader` - The source of the input text.
+/// * `delimiter` - The character separating fields within a record.
+/// * `opts` - The options controlling headers, comments, quoting and trimming.
+///
+pub fn parse_input(
+    reader: impl Read,
+    delimiter: char,
+    opts: &ParseOptions,
+) -> Result<ParsedInput, TabbsError> {
+    let mut records: Vec<Vec<String>> = parse_numbered_input(reader, delimiter, opts)?
+        .into_iter()
+        .map(|(_, record)| record)
+        .collect();
+    let header = if opts.has_header && !records.is_empty() {
+        Some(records.remove(0))
+    } else {
+        None
+    };
+    Ok((header, records))
+}
+
+/// Parse delimited records from `reader` like `parse_input`, pairing each with
+/// the number of the line it starts on, counting from 1.
+///
+/// The header row is not set apart. With a `record_separator` other than `\n`,
+/// records are numbered instead of lines.
+///
+pub fn parse_numbered_input(
+    mut reader: impl Read,
+    delimiter: char,
+    opts: &ParseOptions,
+) -> Result<Vec<(usize, Vec<String>)>, TabbsError> {
+    let mut input = String::new();
+    reader.read_to_string(&mut input)?;
+
+    let mut records = Vec::new();
+    let consumed = Cell::new(0);
+    let mut lines = input
+        .split_inclusive(opts.record_separator)
+        .inspect(|_| consumed.set(consumed.get() + 1));
+    while let Some(line) = lines.next() {
+        let content = line.trim_start();
+        if content
+            .trim_end_matches(opts.record_separator)
+            .trim_end()
+            .is_empty()
+        {
+            continue;
+        }
+        if let Some(comment) = opts.comment {
+            if content.starts_with(comment) {
+                continue;
+            }
+        }
+        let number = consumed.get();
+        records.push((number, parse_record(line, &mut lines, delimiter, opts)));
+    }
+    Ok(records)
+}
+
+/// Return the delimiter declared by a `sep=X` or `#sep=X` directive line, if `line` is one.
+pub fn sep_directive(line: &str) -> Option<char> {
+    let line = line
+        .trim_start_matches('\u{feff}')
+        .trim_end_matches(['\r', '\n']);
+    let value = line
+        .strip_prefix('#')
+        .unwrap_or(line)
+        .strip_prefix("sep=")?;
+    let mut chars = value.chars();
+    match (chars.next(), chars.next()) {
+        (Some(delimiter), None) => Some(delimiter),
+        _ => None,
+    }
+}
+
+/// Parse a self-describing export, whose first line may declare the delimiter and
+/// whose next record is the header row.
+///
+/// A first line of `sep=;` or `#sep=;`, the convention Excel understands, selects
+/// the delimiter in place of `delimiter` and is skipped. Returns the delimiter in
+/// use along with the header and the rows.
+///
+pub fn parse_self_describing(
+    reader: impl Read,
+    delimiter: char,
+    opts: &ParseOptions,
+) -> Result<(char, ParsedInput), TabbsError> {
+    let mut reader = BufReader::new(reader);
+    let mut first_line = String::new();
+    reader.read_line(&mut first_line)?;
+    let opts = ParseOptions {
+        has_header: true,
+        ..opts.clone()
+    };
+    match sep_directive(&first_line) {
+        Some(declared) => Ok((declared, parse_input(reader, declared, &opts)?)),
+        None => {
+            let reader = first_line.as_bytes().chain(reader);
+            Ok((delimiter, parse_input(reader, delimiter, &opts)?))
+        }
+    }
+}
+
+/// Parse NUL-separated fields from `reader`, taking every `column_count` fields
+/// as one row.
+///
+/// A NUL after the last field is optional, and the last row is short when the
+/// fields do not divide evenly. Fields are kept verbatim, line breaks included.
+///
+pub fn parse_nul_fields(
+    mut reader: impl Read,
+    column_count: usize,
+) -> Result<Vec<Vec<String>>, TabbsError> {
+    let mut input = String::new();
+    reader.read_to_string(&mut input)?;
+    let input = input.strip_suffix('\0').unwrap_or(&input);
+    if input.is_empty() || column_count == 0 {
+        return Ok(Vec::new());
+    }
+    let fields: Vec<String> = input.split('\0').map(str::to_string).collect();
+    Ok(fields
+        .chunks(column_count)
+        .map(|chunk| chunk.to_vec())
+        .collect())
+}
+
+/// Parse fixed-width records from `reader`, slicing each line into fields of
+/// `widths` characters.
+///
+/// A line shorter than the widths yields empty trailing fields, and characters
+/// past the last width are ignored. Blank and comment lines are skipped, and
+/// whitespace around each field is trimmed when `opts.trim` is set. Quoting does
+/// not apply.
+///
+pub fn parse_fixed_width_input(
+    mut reader: impl Read,
+    widths: &[usize],
+    opts: &ParseOptions,
+) -> Result<ParsedInput, TabbsError> {
+    let mut input = String::new();
+    reader.read_to_string(&mut input)?;
+
+    let mut records = Vec::new();
+    for line in input.lines() {
+        let content = line.trim_start();
+        if content.trim_end().is_empty() {
+            continue;
+        }
+        if let Some(comment) = opts.comment {
+            if content.starts_with(comment) {
+                continue;
+            }
+        }
+        let mut chars = line.chars();
+        let record = widths
+            .iter()
+            .map(|&width| {
+                let field: String = chars.by_ref().take(width).collect();
+                if opts.trim || (opts.blank_as_empty && field.trim().is_empty()) {
+                    field.trim().to_string()
+                } else {
+                    field
+                }
+            })
+            .collect();
+        records.push(record);
+    }
+
+    let header = if opts.has_header && !records.is_empty() {
+        Some(records.remove(0))
+    } else {
+        None
+    };
+    Ok((header, records))
+}
+
+/// Render `rows` as delimited text that `parse_input` reads back unchanged.
+///
+/// Fields are quoted with `"` when they contain the delimiter, a quote or a line
+/// break, have surrounding whitespace, or would otherwise be lost, such as the empty
+/// fields of a row with no other content. Each record ends with `\n`. Parsing the result with
+/// `delimiter` and the default `ParseOptions` returns `rows`, provided every row
+/// has at least one field.
+///
+pub fn render_csv(rows: &[Vec<String>], delimiter: char) -> String {
+    let mut output = String::new();
+    for row in rows {
+        for (i, field) in row.iter().enumerate() {
+            if i > 0 {
+                output.push(delimiter);
+            }
+            let whitespace = delimiter == ' ';
+            let needs_quotes = field.contains([delimiter, '"', '\n', '\r'])
+                || field.trim() != field
+                || (field.is_empty() && (whitespace || row.iter().all(String::is_empty)))
+                || (whitespace && field.contains('\t'));
+            if needs_quotes {
+                output.push('"');
+                output.push_str(&field.replace('"', "\"\""));
+                output.push('"');
+            } else {
+                output.push_str(field);
+            }
+        }
+        output.push('\n');
+    }
+    output
+}
+
+/// Parse delimited records from `reader` line by line as they arrive, calling
+/// `on_update` with the rows seen so far, and how many there have been in all,
+/// after each new record.
+///
+/// Unlike `parse_input`, the input is never read in one go, so `reader` may be a
+/// pipe that keeps producing lines. A record continues onto the next line while a
+/// quoted field is open. With `limit`, only the last `limit` rows are kept, while
+/// the count still includes the rows dropped before them. Stops at the end of
+/// `reader` or at the first error returned by `on_update`.
+///
+pub fn follow_rows(
+    mut reader: impl BufRead,
+    delimiter: char,
+    opts: &ParseOptions,
+    limit: Option<usize>,
+    mut on_update: impl FnMut(&[Vec<String>], usize) -> Result<(), TabbsError>,
+) -> Result<(), TabbsError> {
+    let opts = ParseOptions {
+        has_header: false,
+        ..opts.clone()
+    };
+    let mut rows = Vec::new();
+    let mut total = 0;
+    let mut pending = String::new();
+    loop {
+        let read = reader.read_line(&mut pending)?;
+        let open_quote = opts
+            .quote
+            .is_some_and(|quote| pending.matches(quote).count() % 2 == 1);
+        if read > 0 && (open_quote || !pending.ends_with('\n')) {
+            continue;
+        }
+        let (_, records) = parse_input(pending.as_bytes(), delimiter, &opts)?;
+        pending.clear();
+        if !records.is_empty() {
+            total += records.len();
+            rows.extend(records);
+            if let Some(limit) = limit {
+                let excess = rows.len().saturating_sub(limit);
+                rows.drain(..excess);
+            }
+            on_update(&rows, total)?;
+        }
+        if read == 0 {
+            return Ok(());
+        }
+    }
+}
+
+/// Parse blocks of `key: value` lines from `reader`, one record per block.
+///
+/// Blocks are separated by blank lines. The header row is the union of all keys
+/// in the order they are first seen, and keys missing from a block leave an empty
+/// cell. Lines without a `:` are reported as invalid data.
+///
+pub fn parse_kv_input(mut reader: impl Read) -> Result<ParsedInput, TabbsError> {
+    let mut input = String::new();
+    reader.read_to_string(&mut input)?;
+
+    let mut keys: Vec<String> = Vec::new();
+    let mut records: Vec<Vec<(usize, String)>> = Vec::new();
+    let mut record = Vec::new();
+    for (number, line) in input.lines().enumerate() {
+        if line.trim().is_empty() {
+            if !record.is_empty() {
+                records.push(std::mem::take(&mut record));
+            }
+            continue;
+        }
+        let (key, value) = line.split_once(':').ok_or_else(|| TabbsError::Parse {
+            line: number + 1,
+            message: "expected `key: value`".to_string(),
+        })?;
+        let key = key.trim();
+        let index = match keys.iter().position(|k| k == key) {
+            Some(index) => index,
+            None => {
+                keys.push(key.to_string());
+                keys.len() - 1
+            }
+        };
+        record.push((index, value.trim().to_string()));
+    }
+    if !record.is_empty() {
+        records.push(record);
+    }
+
+    let rows = records
+        .into_iter()
+        .map(|record| {
+            let mut row = vec![String::new(); keys.len()];
+            for (index, value) in record {
+                row[index] = value;
+            }
+            row
+        })
+        .collect();
+    Ok((Some(keys), rows))
+}
+
+/// Parse a single record starting at `line`, pulling further lines from `lines`
+/// while a quoted field is still open.
+fn parse_record<'a>(
+    line: &'a str,
+    lines: &mut impl Iterator<Item = &'a str>,
+    delimiter: char,
+    opts: &ParseOptions,
+) -> Vec<String> {
+    let whitespace = delimiter == ' ';
+    let mut fields = Vec::new();
+    let mut field = Field::default();
+    let mut in_quotes = false;
+    let mut line = line;
+
+    loop {
+        let mut chars = line.chars().peekable();
+        while let Some(c) = chars.next() {
+            if in_quotes {
+                if Some(c) == opts.quote {
+                    if chars.peek() == opts.quote.as_ref() {
+                        field.text.push(c);
+                        chars.next();
+                    } else {
+                        in_quotes = false;
+                        field.closed_at = Some(field.text.len());
+                    }
+                } else {
+                    field.text.push(c);
+                }
+            } else if Some(c) == opts.quote && field.is_blank(opts.trim) {
+                field.text.clear();
+                field.quoted = true;
+                in_quotes = true;
+            } else if c == '\\'
+                && opts.backslash_escape
+                && matches!(chars.peek(), Some(&next) if next == delimiter || next == '\\')
+            {
+                field.text.push(chars.next().unwrap());
+            } else if ends_record(c, chars.peek(), opts.record_separator) {
+                continue;
+            } else if whitespace && (c == ' ' || c == '\t') {
+                if !field.text.is_empty() || field.quoted {
+                    fields.push(field.finish(opts));
+                    field = Field::default();
+                }
+            } else if c == delimiter {
+                fields.push(field.finish(opts));
+                field = Field::default();
+            } else {
+                field.text.push(c);
+            }
+        }
+
+        if !in_quotes {
+            break;
+        }
+        match lines.next() {
+            Some(next) => line = next,
+            None => break,
+        }
+    }
+
+    if !whitespace || !field.text.is_empty() || field.quoted {
+        fields.push(field.finish(opts));
+    }
+    fields
+}
+
+/// Whether `c`, followed by `next`, is (the start of) the end of a record.
+fn ends_record(c: char, next: Option<&char>, separator: char) -> bool {
+    match separator {
+        '\n' => c == '\n' || (c == '\r' && next == Some(&'\n')),
+        separator => c == separator,
+    }
+}
+
+/// A field being accumulated by `parse_record`.
+#[derive(Default)]
+struct Field {
+    text: String,
+    quoted: bool,
+    /// The length of `text` when its closing quote was read.
+    closed_at: Option<usize>,
+}
+
+impl Field {
+    /// Whether only (trimmable) whitespace has been read so far, so a quote may open the field.
+    fn is_blank(&self, trim: bool) -> bool {
+        !self.quoted && (self.text.is_empty() || (trim && self.text.trim().is_empty()))
+    }
+
+    fn finish(mut self, opts: &ParseOptions) -> String {
+        if opts.blank_as_empty && !self.quoted && self.text.trim().is_empty() {
+            return String::new();
+        }
+        if !opts.trim {
+            return self.text;
+        }
+        match self.closed_at {
+            Some(len) => {
+                let trailing = self.text[len..].trim_end().len();
+                self.text.truncate(len + trailing);
+                self.text
+            }
+            None => self.text.trim().to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(input: &str, delimiter: char, opts: &ParseOptions) -> Vec<Vec<String>> {
+        parse_input(input.as_bytes(), delimiter, opts).unwrap().1
+    }
+
+    #[test]
+    fn test_parse_quoted_fields() {
+        let rows = parse(
+            "\"smith, jack\",35,\"said \"\"hi\"\"\"\n\"multi\nline\", 50 ,ok\n",
+            ',',
+            &ParseOptions::default(),
+        );
+        assert_eq!(
+            rows,
+            vec![
+                vec!["smith, jack", "35", "said \"hi\""],
+                vec!["multi\nline", "50", "ok"],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_crlf_and_header() {
+        let opts = ParseOptions {
+            has_header: true,
+            ..ParseOptions::default()
+        };
+        let (header, rows) = parse_input(
+            "name,age\r\njack,35\r\n\r\njane,50\r\n".as_bytes(),
+            ',',
+            &opts,
+        )
+        .unwrap();
+        assert_eq!(header, Some(vec!["name".to_string(), "age".to_string()]));
+        assert_eq!(rows, vec![vec!["jack", "35"], vec!["jane", "50"]]);
+    }
+
+    #[test]
+    fn test_parse_comments_and_whitespace() {
+        let opts = ParseOptions {
+            comment: Some('#'),
+            ..ParseOptions::default()
+        };
+        let rows = parse(
+            "# generated\njack   35\tneat\n  # indented\njane 50 cool\n",
+            ' ',
+            &opts,
+        );
+        assert_eq!(
+            rows,
+            vec![vec!["jack", "35", "neat"], vec!["jane", "50", "cool"]]
+        );
+    }
+
+    #[test]
+    fn test_parse_kv_blocks() {
+        let input = "name: jack\nage: 35\n\n\nname: jane\ntext: cool: very\n";
+        let (header, rows) = parse_kv_input(input.as_bytes()).unwrap();
+        assert_eq!(
+            header,
+            Some(vec![
+                "name".to_string(),
+                "age".to_string(),
+                "text".to_string()
+            ])
+        );
+        assert_eq!(
+            rows,
+            vec![vec!["jack", "35", ""], vec!["jane", "", "cool: very"]]
+        );
+        assert!(parse_kv_input("name jack\n".as_bytes()).is_err());
+    }
+
+    #[test]
+    fn test_parse_backslash_escapes() {
+        let opts = ParseOptions {
+            backslash_escape: true,
+            ..ParseOptions::default()
+        };
+        let rows = parse("a\\,b,c\nC:\\\\dir,\\n\n", ',', &opts);
+        assert_eq!(rows, vec![vec!["a,b", "c"], vec!["C:\\dir", "\\n"]]);
+        let rows = parse("a\\,b,c\n", ',', &ParseOptions::default());
+        assert_eq!(rows, vec![vec!["a\\", "b", "c"]]);
+    }
+
+    #[test]
+    fn test_parse_fixed_width_input() {
+        let input = "jack      35   likes long walks\njane      50\n";
+        let (header, rows) =
+            parse_fixed_width_input(input.as_bytes(), &[10, 5, 20], &ParseOptions::default())
+                .unwrap();
+        assert_eq!(header, None);
+        assert_eq!(
+            rows,
+            vec![
+                vec!["jack", "35", "likes long walks"],
+                vec!["jane", "50", ""]
+            ]
+        );
+
+        let opts = ParseOptions {
+            trim: false,
+            ..ParseOptions::default()
+        };
+        let (_, rows) = parse_fixed_width_input("ab  cd\n".as_bytes(), &[4, 2], &opts).unwrap();
+        assert_eq!(rows, vec![vec!["ab  ", "cd"]]);
+    }
+
+    #[test]
+    fn test_blank_as_empty() {
+        let opts = ParseOptions {
+            trim: false,
+            blank_as_empty: true,
+            ..ParseOptions::default()
+        };
+        assert_eq!(
+            parse("jack,   , neat\njane,\"  \",cool\n", ',', &opts),
+            vec![vec!["jack", "", " neat"], vec!["jane", "  ", "cool"]]
+        );
+        let (_, rows) =
+            parse_fixed_width_input("ab    cd\n".as_bytes(), &[2, 4, 2], &opts).unwrap();
+        assert_eq!(rows, vec![vec!["ab", "", "cd"]]);
+    }
+
+    #[test]
+    fn test_follow_rows() {
+        let (reader, mut writer) = std::io::pipe().unwrap();
+        let (updated, wait_for_update) = std::sync::mpsc::channel();
+        let feeder = std::thread::spawn(move || {
+            for line in ["jack,35\n", "\"multi\n", "line\",50\n", "erin,20\n"] {
+                std::io::Write::write_all(&mut writer, line.as_bytes()).unwrap();
+                if !line.starts_with("\"multi") {
+                    wait_for_update.recv().unwrap();
+                }
+            }
+        });
+        let (mut updates, mut totals) = (Vec::new(), Vec::new());
+        follow_rows(
+            std::io::BufReader::new(reader),
+            ',',
+            &ParseOptions::default(),
+            Some(2),
+            |rows, total| {
+                updates.push(rows.to_vec());
+                totals.push(total);
+                updated.send(()).unwrap();
+                Ok(())
+            },
+        )
+        .unwrap();
+        feeder.join().unwrap();
+        assert_eq!(
+            updates,
+            vec![
+                vec![vec!["jack", "35"]],
+                vec![vec!["jack", "35"], vec!["multi\nline", "50"]],
+                vec![vec!["multi\nline", "50"], vec!["erin", "20"]],
+            ]
+        );
+        assert_eq!(totals, vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn test_render_csv_round_trip() {
+        // A small xorshift generator keeps the generated cases reproducible.
+        let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+        let mut next = |bound: usize| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            (state % bound as u64) as usize
+        };
+        let alphabet = [
+            'a', 'Z', '7', ' ', '\t', ',', ';', '"', '\n', '\r', '\\', 'é', '表',
+        ];
+        for case in 0..2000 {
+            let delimiter = [',', ';', '\t', ' '][case % 4];
+            let rows: Vec<Vec<String>> = (0..1 + next(4))
+                .map(|_| {
+                    (0..1 + next(4))
+                        .map(|_| {
+                            (0..next(6))
+                                .map(|_| alphabet[next(alphabet.len())])
+                                .collect()
+                        })
+                        .collect()
+                })
+                .collect();
+            let rendered = render_csv(&rows, delimiter);
+            assert_eq!(
+                parse(&rendered, delimiter, &ParseOptions::default()),
+                rows,
+                "round trip failed for {:?}",
+                rendered
+            );
+        }
+        assert_eq!(
+            render_csv(
+                &[vec![
+                    "a,b".to_string(),
+                    "say \"hi\"".to_string(),
+                    String::new()
+                ]],
+                ','
+            ),
+            "\"a,b\",\"say \"\"hi\"\"\",\n"
+        );
+    }
+
+    #[test]
+    fn test_record_separator() {
+        let opts = ParseOptions {
+            record_separator: '\0',
+            ..ParseOptions::default()
+        };
+        assert_eq!(
+            parse("./a b,1\0./multi\nline,2\0\0./c,3", ',', &opts),
+            vec![
+                vec!["./a b", "1"],
+                vec!["./multi\nline", "2"],
+                vec!["./c", "3"]
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_numbered_input() {
+        let opts = ParseOptions {
+            comment: Some('#'),
+            ..ParseOptions::default()
+        };
+        let records = parse_numbered_input(
+            "jack,35\n# note\n\"multi\nline\",50\n\nerin,20\n".as_bytes(),
+            ',',
+            &opts,
+        )
+        .unwrap();
+        let numbers: Vec<usize> = records.iter().map(|(number, _)| *number).collect();
+        assert_eq!(numbers, vec![1, 3, 6]);
+        assert_eq!(records[1].1, vec!["multi\nline", "50"]);
+    }
+
+    #[test]
+    fn test_parse_nul_fields() {
+        let rows = parse_nul_fields("./a b\0small\0./multi\nline\0large\0".as_bytes(), 2).unwrap();
+        assert_eq!(
+            rows,
+            vec![vec!["./a b", "small"], vec!["./multi\nline", "large"]]
+        );
+        let rows = parse_nul_fields("a\0b\0c".as_bytes(), 2).unwrap();
+        assert_eq!(rows, vec![vec!["a", "b"], vec!["c"]]);
+        assert!(parse_nul_fields("".as_bytes(), 2).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_parse_self_describing() {
+        let input = "sep=;\nname;age\njack;35\n";
+        let (delimiter, (header, rows)) =
+            parse_self_describing(input.as_bytes(), ',', &ParseOptions::default()).unwrap();
+        assert_eq!(delimiter, ';');
+        assert_eq!(header, Some(vec!["name".to_string(), "age".to_string()]));
+        assert_eq!(rows, vec![vec!["jack", "35"]]);
+
+        let input = "name,age\njack,35\n";
+        let (delimiter, (header, rows)) =
+            parse_self_describing(input.as_bytes(), ',', &ParseOptions::default()).unwrap();
+        assert_eq!(delimiter, ',');
+        assert_eq!(header, Some(vec!["name".to_string(), "age".to_string()]));
+        assert_eq!(rows, vec![vec!["jack", "35"]]);
+
+        assert_eq!(sep_directive("\u{feff}#sep=\t\r\n"), Some('\t'));
+        assert_eq!(sep_directive("sep=;;"), None);
+    }
+}
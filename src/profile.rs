@@ -0,0 +1,173 @@
+//! Loading option defaults from a TOML profile.
+//!
+//! A profile holds `key = value` lines whose keys name command line options
+//! without their leading dashes, such as `border = "rounded"` for
+//! `--border rounded`. Only this flat subset of TOML is read; tables are
+//! rejected.
+
+use crate::cli::{ParsedOption, OPTIONS};
+use crate::io_context;
+use std::fs;
+use std::path::Path;
+use tabbs::TabbsError;
+
+/// A value on the right-hand side of a profile line.
+#[derive(Debug, PartialEq)]
+enum Value {
+    Bool(bool),
+    Text(String),
+}
+
+/// Read the profile at `path` as options, as if they were given on the command line.
+pub fn load_profile(path: &Path) -> Result<Vec<ParsedOption>, TabbsError> {
+    let text = fs::read_to_string(path)
+        .map_err(|err| io_context(err.into(), &format!("Failed to read {}", path.display())))?;
+    parse_profile(&text).map_err(|(line, message)| {
+        TabbsError::Config(format!("{}:{}: {}", path.display(), line, message))
+    })
+}
+
+/// Parse the lines of a profile into options, or return the failing line number and why.
+///
+/// `true` turns a flag on and `false` leaves it off. Numbers and strings become
+/// the option's value, and arrays are joined with commas, so
+/// `hide_header = ["id", "zip"]` is `--hide-header id,zip`.
+///
+fn parse_profile(text: &str) -> Result<Vec<ParsedOption>, (usize, String)> {
+    let mut options = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let fail = |message: String| (i + 1, message);
+        if line.starts_with('[') {
+            return Err(fail("tables are not supported".to_string()));
+        }
+        let (key, value) = line
+            .split_once('=')
+            .ok_or_else(|| fail("expected key = value".to_string()))?;
+        let key = key.trim();
+        let name = format!("--{}", key.replace('_', "-"));
+        let spec = OPTIONS
+            .iter()
+            .find(|spec| spec.name == name || spec.alias == Some(name.as_str()))
+            .filter(|spec| spec.name != "--profile")
+            .ok_or_else(|| fail(format!("unknown option: {}", key)))?;
+        let (value, rest) = parse_value(value.trim()).map_err(fail)?;
+        if !rest.trim_start().is_empty() && !rest.trim_start().starts_with('#') {
+            return Err(fail(format!(
+                "unexpected text after value: {}",
+                rest.trim()
+            )));
+        }
+        match (spec.value, value) {
+            (None, Value::Bool(true)) => options.push((spec.name, None)),
+            (None, Value::Bool(false)) => {}
+            (None, Value::Text(_)) => return Err(fail(format!("{} must be true or false", key))),
+            (Some(_), Value::Text(text)) => options.push((spec.name, Some(text))),
+            (Some(_), Value::Bool(_)) => return Err(fail(format!("{} needs a value", key))),
+        }
+    }
+    Ok(options)
+}
+
+/// Parse a scalar or an array of scalars from the start of `s`, returning the rest.
+fn parse_value(s: &str) -> Result<(Value, &str), String> {
+    let Some(mut rest) = s.strip_prefix('[') else {
+        return parse_scalar(s);
+    };
+    let mut items = Vec::new();
+    loop {
+        rest = rest.trim_start();
+        if let Some(after) = rest.strip_prefix(']') {
+            return Ok((Value::Text(items.join(",")), after));
+        }
+        match parse_scalar(rest)? {
+            (Value::Text(item), after) => {
+                items.push(item);
+                rest = after.trim_start();
+            }
+            (Value::Bool(_), _) => return Err("arrays may only hold strings and numbers".into()),
+        }
+        if let Some(after) = rest.strip_prefix(',') {
+            rest = after;
+        } else if !rest.starts_with(']') {
+            return Err("expected , or ] in array".to_string());
+        }
+    }
+}
+
+/// Parse a string, number or boolean from the start of `s`, returning the rest.
+fn parse_scalar(s: &str) -> Result<(Value, &str), String> {
+    if let Some(rest) = s.strip_prefix('"') {
+        let mut text = String::new();
+        let mut chars = rest.char_indices();
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '"' => return Ok((Value::Text(text), &rest[i + 1..])),
+                '\\' => match chars.next().map(|(_, c)| c) {
+                    Some('n') => text.push('\n'),
+                    Some('t') => text.push('\t'),
+                    Some(c @ ('"' | '\\')) => text.push(c),
+                    _ => return Err("invalid escape in string".to_string()),
+                },
+                c => text.push(c),
+            }
+        }
+        return Err("unterminated string".to_string());
+    }
+    if let Some(rest) = s.strip_prefix('\'') {
+        let end = rest.find('\'').ok_or("unterminated string")?;
+        return Ok((Value::Text(rest[..end].to_string()), &rest[end + 1..]));
+    }
+    let end = s
+        .find(|c: char| c == ',' || c == ']' || c == '#' || c.is_whitespace())
+        .unwrap_or(s.len());
+    let (token, rest) = s.split_at(end);
+    match token {
+        "true" => Ok((Value::Bool(true), rest)),
+        "false" => Ok((Value::Bool(false), rest)),
+        _ if token.parse::<f64>().is_ok() => Ok((Value::Text(token.to_string()), rest)),
+        _ => Err(format!("invalid value: {}", token)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::Args;
+
+    #[test]
+    fn test_parse_profile() {
+        let profile = parse_profile(
+            "# house style\n\
+             border = \"rounded\"\n\
+             header_color = 'cyan'  # headers\n\
+             width = 60\n\
+             hide-header = [\"id\", \"zip\"]\n\
+             legend = true\n\
+             repair = false\n",
+        )
+        .unwrap();
+        let args = Args::parse(&["--header-color".to_string(), "red".to_string()])
+            .unwrap()
+            .with_defaults(profile);
+        assert_eq!(args.value("--border"), Some("rounded"));
+        assert_eq!(args.value("--header-color"), Some("red"));
+        assert_eq!(args.value("--width"), Some("60"));
+        assert_eq!(args.value("--hide-header"), Some("id,zip"));
+        assert!(args.flag("--legend"));
+        assert!(!args.flag("--repair"));
+
+        assert_eq!(parse_profile("[style]").unwrap_err().0, 1);
+        assert_eq!(parse_profile("\nbogus = 1").unwrap_err().0, 2);
+        assert!(parse_profile("legend = \"yes\"").is_err());
+        assert!(parse_profile("border = \"rounded").is_err());
+        assert!(parse_profile("border = rounded").is_err());
+
+        let missing = std::env::temp_dir().join("tabbs-missing-profile.toml");
+        let err = load_profile(&missing).unwrap_err().to_string();
+        assert!(err.contains(&format!("Failed to read {}", missing.display())));
+    }
+}
@@ -0,0 +1,100 @@
+//! A minimal HTTP client for reading remote input with `-f`.
+
+use std::io::{self, Read, Write};
+use std::net::TcpStream;
+use std::time::Duration;
+
+/// How long to wait for the server before giving up.
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Fetch the body of `url` with a plain HTTP/1.0 `GET` request.
+///
+/// Only `http://` URLs are supported; `https://` is rejected with an error since
+/// there is no TLS implementation available. Non-2xx responses are errors.
+///
+pub fn fetch(url: &str) -> io::Result<Vec<u8>> {
+    let error = |message: String| io::Error::other(message);
+    if url.starts_with("https://") {
+        return Err(error(format!(
+            "cannot fetch {}: https URLs are not supported",
+            url
+        )));
+    }
+    let rest = url
+        .strip_prefix("http://")
+        .ok_or_else(|| error(format!("not an http URL: {}", url)))?;
+    let (authority, path) = match rest.find('/') {
+        Some(index) => rest.split_at(index),
+        None => (rest, "/"),
+    };
+    let address = if authority.contains(':') {
+        authority.to_string()
+    } else {
+        format!("{}:80", authority)
+    };
+
+    let mut stream = TcpStream::connect(&address)
+        .map_err(|err| error(format!("cannot connect to {}: {}", authority, err)))?;
+    stream.set_read_timeout(Some(TIMEOUT))?;
+    write!(
+        stream,
+        "GET {} HTTP/1.0\r\nHost: {}\r\nUser-Agent: tabbs\r\nConnection: close\r\n\r\n",
+        path, authority
+    )?;
+
+    let mut response = Vec::new();
+    stream.read_to_end(&mut response)?;
+    let header_end = response
+        .windows(4)
+        .position(|window| window == b"\r\n\r\n")
+        .ok_or_else(|| error(format!("malformed response from {}", url)))?;
+    let head = String::from_utf8_lossy(&response[..header_end]);
+    let status = head.lines().next().unwrap_or_default();
+    let code = status.split_whitespace().nth(1).unwrap_or_default();
+    if !code.starts_with('2') {
+        return Err(error(format!("request to {} failed: {}", url, status)));
+    }
+    Ok(response[header_end + 4..].to_vec())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::TcpListener;
+    use std::thread;
+
+    /// Serve a single canned `response` on a local port and return its base URL.
+    fn mock_server(response: &'static str) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            // Read the whole request so that closing the socket does not reset it.
+            let mut request = Vec::new();
+            let mut chunk = [0; 1024];
+            while !request.ends_with(b"\r\n\r\n") {
+                let n = stream.read(&mut chunk).unwrap();
+                if n == 0 {
+                    break;
+                }
+                request.extend_from_slice(&chunk[..n]);
+            }
+            stream.write_all(response.as_bytes()).unwrap();
+        });
+        format!("http://{}", address)
+    }
+
+    #[test]
+    fn test_fetch_from_mock_server() {
+        let url =
+            mock_server("HTTP/1.0 200 OK\r\nContent-Type: text/csv\r\n\r\njack,35\njane,50\n");
+        let body = fetch(&format!("{}/people.csv", url)).unwrap();
+        assert_eq!(body, b"jack,35\njane,50\n");
+
+        let url = mock_server("HTTP/1.0 404 Not Found\r\n\r\nmissing");
+        let err = fetch(&format!("{}/missing.csv", url)).unwrap_err();
+        assert!(err.to_string().contains("404 Not Found"));
+
+        assert!(fetch("https://example.com/a.csv").is_err());
+    }
+}
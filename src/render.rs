@@ -0,0 +1,3485 @@
+//! Rendering of parsed rows as a bordered table.
+
+use crate::color::{color_mark, gradient_color, paint, ColorScale, ValueColors};
+use crate::error::TabbsError;
+use crate::number::{NumberFormat, NumberLocale};
+use crate::text::{
+    block_width, display_width, hyperlink, looks_like_url, trimmed_block_width, truncate_middle,
+    truncate_to_width, wrap_to_width,
+};
+use crate::types::{detect_column_types, detect_type, parse_bool, ColumnType};
+use colored::Colorize;
+use std::borrow::Cow;
+use std::io::{self, Write};
+use std::str::FromStr;
+
+/// The layout used by `print_table_to_writer`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// A table framed by borders with a rule below the header.
+    #[default]
+    Table,
+    /// Space-aligned columns with no borders or header rule, like `column -t`.
+    Plain,
+    /// Width-padded columns separated by a single tab, readable in a terminal and
+    /// split back into fields on tabs.
+    AlignedTsv,
+    /// A LaTeX `tabular` environment.
+    Latex,
+    /// Confluence and Jira wiki markup, with `||` around header cells and `|` around cells.
+    Jira,
+    /// A JSON array with one object per row, keyed by column name.
+    Json,
+}
+
+/// The horizontal alignment of text within a cell.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Align {
+    #[default]
+    Left,
+    Right,
+    Center,
+}
+
+impl FromStr for Align {
+    type Err = TabbsError;
+
+    /// Parse `l`, `r` or `c`, or the full words `left`, `right` or `center`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "l" | "left" => Ok(Align::Left),
+            "r" | "right" => Ok(Align::Right),
+            "c" | "center" => Ok(Align::Center),
+            _ => Err(TabbsError::Config(format!("Invalid alignment: {}", s))),
+        }
+    }
+}
+
+/// The characters used to draw the borders and rules of a table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BorderChars {
+    /// The line of the top, bottom and header rules.
+    pub horizontal: char,
+    /// The line of the outer borders and the dividers between columns.
+    pub vertical: char,
+    pub top_left: char,
+    pub top_right: char,
+    pub bottom_left: char,
+    pub bottom_right: char,
+    /// Where the header rule meets the left border.
+    pub left_junction: char,
+    /// Where the header rule meets the right border.
+    pub right_junction: char,
+    /// Where the top rule meets a divider between columns.
+    pub top_junction: char,
+    /// Where the header rule crosses a divider between columns.
+    pub junction: char,
+    /// Where the bottom rule meets a divider between columns.
+    pub bottom_junction: char,
+    /// The divider after the first column when it is a row header.
+    pub row_header_vertical: char,
+}
+
+impl BorderChars {
+    /// Plain `+`, `-` and `|` borders that display everywhere.
+    pub const ASCII: BorderChars = BorderChars {
+        horizontal: '-',
+        vertical: '|',
+        top_left: '+',
+        top_right: '+',
+        bottom_left: '+',
+        bottom_right: '+',
+        left_junction: '+',
+        right_junction: '+',
+        top_junction: '+',
+        junction: '+',
+        bottom_junction: '+',
+        row_header_vertical: '#',
+    };
+
+    /// Unicode box drawing lines.
+    pub const UNICODE: BorderChars = BorderChars {
+        horizontal: '─',
+        vertical: '│',
+        top_left: '┌',
+        top_right: '┐',
+        bottom_left: '└',
+        bottom_right: '┘',
+        left_junction: '├',
+        right_junction: '┤',
+        top_junction: '┬',
+        junction: '┼',
+        bottom_junction: '┴',
+        row_header_vertical: '┃',
+    };
+
+    /// Unicode box drawing lines with rounded outer corners.
+    pub const ROUNDED: BorderChars = BorderChars {
+        top_left: '╭',
+        top_right: '╮',
+        bottom_left: '╰',
+        bottom_right: '╯',
+        ..BorderChars::UNICODE
+    };
+
+    /// Thick Unicode box drawing lines.
+    pub const HEAVY: BorderChars = BorderChars {
+        horizontal: '━',
+        vertical: '┃',
+        top_left: '┏',
+        top_right: '┓',
+        bottom_left: '┗',
+        bottom_right: '┛',
+        left_junction: '┣',
+        right_junction: '┫',
+        top_junction: '┳',
+        junction: '╋',
+        bottom_junction: '┻',
+        row_header_vertical: '║',
+    };
+
+    /// Whether every character is ASCII.
+    pub fn is_ascii(&self) -> bool {
+        [
+            self.horizontal,
+            self.vertical,
+            self.top_left,
+            self.top_right,
+            self.bottom_left,
+            self.bottom_right,
+            self.left_junction,
+            self.right_junction,
+            self.top_junction,
+            self.junction,
+            self.bottom_junction,
+            self.row_header_vertical,
+        ]
+        .iter()
+        .all(char::is_ascii)
+    }
+}
+
+impl Default for BorderChars {
+    fn default() -> Self {
+        BorderChars::ASCII
+    }
+}
+
+impl FromStr for BorderChars {
+    type Err = TabbsError;
+
+    /// Parse the name of a border style: `ascii`, `unicode`, `rounded` or `heavy`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "ascii" => Ok(BorderChars::ASCII),
+            "unicode" => Ok(BorderChars::UNICODE),
+            "rounded" => Ok(BorderChars::ROUNDED),
+            "heavy" => Ok(BorderChars::HEAVY),
+            _ => Err(TabbsError::Config(format!("Invalid border style: {}", s))),
+        }
+    }
+}
+
+/// A named preset of a border style and colors for `TableOptions`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Theme {
+    pub border: BorderChars,
+    pub header_color: Option<&'static str>,
+    pub stripe_color: Option<&'static str>,
+}
+
+impl Theme {
+    /// ASCII borders without colors.
+    pub const MINIMAL: Theme = Theme {
+        border: BorderChars::ASCII,
+        header_color: None,
+        stripe_color: None,
+    };
+
+    /// Thick borders with a yellow header.
+    pub const HEAVY: Theme = Theme {
+        border: BorderChars::HEAVY,
+        header_color: Some("yellow"),
+        stripe_color: None,
+    };
+
+    /// Rounded borders with a cyan header and blue stripes.
+    pub const COLORFUL: Theme = Theme {
+        border: BorderChars::ROUNDED,
+        header_color: Some("cyan"),
+        stripe_color: Some("blue"),
+    };
+}
+
+impl FromStr for Theme {
+    type Err = TabbsError;
+
+    /// Parse the name of a theme: `minimal`, `heavy` or `colorful`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "minimal" => Ok(Theme::MINIMAL),
+            "heavy" => Ok(Theme::HEAVY),
+            "colorful" => Ok(Theme::COLORFUL),
+            _ => Err(TabbsError::Config(format!("Unknown theme: {}", s))),
+        }
+    }
+}
+
+/// Options controlling how `print_table_to_writer` renders a table.
+#[derive(Debug, Default, Clone)]
+pub struct TableOptions {
+    /// The layout of the rendered table.
+    pub format: Format,
+    /// The color of the header text.
+    pub header_color: Option<String>,
+    /// The color of the cell text.
+    pub cell_color: Option<String>,
+    /// The color of every second row, starting with the second.
+    pub stripe_color: Option<String>,
+    /// The color of every second column, starting with the second.
+    pub alt_column_color: Option<String>,
+    /// Fixed widths per column; `None` entries are sized from their content.
+    pub column_widths: Vec<Option<usize>>,
+    /// Upper bounds on column widths; `None` entries are not capped.
+    pub max_column_widths: Vec<Option<usize>>,
+    /// Names of columns whose header cell is left blank; their cells are still shown.
+    pub hidden_headers: Vec<String>,
+    /// Wrap header text at this many display columns, independently of the cells.
+    pub header_wrap: Option<usize>,
+    /// The characters used to draw borders in the `Table` format.
+    pub border: BorderChars,
+    /// Show the first column in bold, followed by a heavier divider, as row labels.
+    pub row_header: bool,
+    /// Draw only the outer border, omitting interior dividers and the header rule.
+    pub outer_only: bool,
+    /// Omit the rule between the header and the body while keeping the borders.
+    pub no_header_rule: bool,
+    /// The alignment of cells in columns without their own alignment.
+    pub align: Align,
+    /// Alignments per column; `None` entries use `align`.
+    pub column_alignments: Vec<Option<Align>>,
+    /// Right-align numeric and center boolean columns that have no alignment of their own.
+    pub auto_align: bool,
+    /// Declared types per column; `None` entries are detected from the rows when needed.
+    pub column_types: Vec<Option<ColumnType>>,
+    /// Threshold coloring rules for numeric columns.
+    pub color_scales: Vec<ColorScale>,
+    /// Colors for exact cell values of a column.
+    pub value_colors: Vec<ValueColors>,
+    /// Draw a rule between consecutive rows whose cells in the column of this name differ.
+    pub group_lines: Option<String>,
+    /// Colors per row that take precedence over every other cell color except `cell_colors`.
+    pub row_colors: Vec<Option<String>>,
+    /// Colors per cell, by row and then column, that take precedence over every other color.
+    pub cell_colors: Vec<Vec<Option<String>>>,
+    /// Rows shown dimmed, on top of any color their cells have.
+    pub dimmed_rows: Vec<bool>,
+    /// Format patterns for the numeric cells of named columns.
+    pub number_formats: Vec<NumberFormat>,
+    /// Names of numeric columns colored on a blue to red gradient from their smallest
+    /// to their largest value.
+    pub heatmaps: Vec<String>,
+    /// Text written before the non-empty cells of a column, as `(column, prefix)` pairs.
+    pub prefixes: Vec<(String, String)>,
+    /// Text written after the non-empty cells of a column, as `(column, suffix)` pairs.
+    pub suffixes: Vec<(String, String)>,
+    /// Write numbers with the digit grouping and decimal separator of this locale.
+    pub locale: Option<NumberLocale>,
+    /// Print a key below the table explaining the active color rules.
+    pub legend: bool,
+    /// Append a textual mark such as `(!)` to cells colored by a rule, instead of the color.
+    pub marks: bool,
+    /// A message shown across the body, centered, when there are no rows.
+    pub empty_message: Option<String>,
+    /// The number of rows left out of the table, noted in a final row when non-zero.
+    pub omitted_rows: usize,
+    /// Leave trailing whitespace out of the widths of cells, while still printing it where it fits.
+    pub rtrim_for_width: bool,
+    /// The number of threads used to measure column widths; 0 or 1 measures sequentially.
+    pub jobs: usize,
+    /// A title centered in a row across every column, inside the borders above the header.
+    pub banner: Option<String>,
+    /// A line of text written below the table, wrapped to the table width.
+    pub caption: Option<String>,
+    /// The alignment of the caption within the table width.
+    pub caption_align: Align,
+    /// Show cells holding a URL as terminal hyperlinks.
+    pub hyperlinks: bool,
+    /// Widen the columns of a narrower table so it is this many display columns wide.
+    pub pad_to: Option<usize>,
+    /// Split a table wider than this many display columns into pages of columns.
+    pub page_width: Option<usize>,
+    /// The number of leading columns repeated on every page of columns.
+    pub frozen_columns: usize,
+    /// Write numeric and boolean cells as JSON numbers and booleans instead of strings.
+    pub json_typed: bool,
+    /// Cut the middle out of cells that do not fit their column instead of their end,
+    /// keeping this share of the width for the start of the cell.
+    pub truncate_middle: Option<f64>,
+    /// Leave out the newline after the last line of the table.
+    pub no_trailing_newline: bool,
+}
+
+impl TableOptions {
+    /// Return the header text shown for each column, wrapped or blanked as configured.
+    fn header_texts(&self, column_names: &[&str]) -> Vec<String> {
+        column_names
+            .iter()
+            .map(|name| match self.header_wrap {
+                _ if self.hidden_headers.iter().any(|hidden| hidden == name) => String::new(),
+                Some(width) => wrap_to_width(name, width),
+                None => name.to_string(),
+            })
+            .collect()
+    }
+
+    /// Return the display width of every cell of `rows`, in the order of `spanned_cells`.
+    ///
+    /// With `rtrim_for_width`, trailing whitespace is not counted.
+    ///
+    fn measure_cells(&self, column_count: usize, rows: &[Vec<String>]) -> Vec<Vec<usize>> {
+        let width_of = if self.rtrim_for_width {
+            trimmed_block_width
+        } else {
+            block_width
+        };
+        if self.jobs > 1 {
+            parallel_measure_cells(column_count, rows, self.jobs, width_of)
+        } else {
+            measure_cells(column_count, rows, width_of)
+        }
+    }
+
+    /// Return the width of each column: the widest of its header and cells, unless fixed.
+    ///
+    /// `measured` holds the width of every cell, as returned by `measure_cells`.
+    ///
+    fn measure_columns(
+        &self,
+        header_texts: &[String],
+        rows: &[Vec<String>],
+        measured: &[Vec<usize>],
+    ) -> Vec<usize> {
+        let mut column_widths: Vec<usize> = header_texts.iter().map(|s| block_width(s)).collect();
+        let cell_widths = cell_widths(header_texts.len(), rows, measured);
+        for (width, cell_width) in column_widths.iter_mut().zip(cell_widths) {
+            *width = (*width).max(cell_width);
+        }
+        let separator = match self.format {
+            Format::Plain => 2,
+            Format::AlignedTsv => 1,
+            _ => 3,
+        };
+        fit_spans(&mut column_widths, rows, separator);
+        for (width, cap) in column_widths.iter_mut().zip(&self.max_column_widths) {
+            if let Some(cap) = cap {
+                *width = (*width).min(*cap);
+            }
+        }
+        for (width, fixed) in column_widths.iter_mut().zip(&self.column_widths) {
+            if let Some(fixed) = fixed {
+                *width = *fixed;
+            }
+        }
+        column_widths
+    }
+
+    /// Return the color that a cell, row, value or threshold rule gives `cell` in `column`, if any.
+    fn rule_color_for<'a>(
+        &'a self,
+        rules: &ColumnRules<'a>,
+        row: usize,
+        column: usize,
+        cell: &str,
+    ) -> Option<&'a str> {
+        let cell_color = self
+            .cell_colors
+            .get(row)
+            .and_then(|colors| colors.get(column))
+            .and_then(|color| color.as_deref());
+        let row_color = self.row_colors.get(row).and_then(|color| color.as_deref());
+        cell_color
+            .or(row_color)
+            .or_else(|| rules.color_for(column, cell))
+    }
+
+    /// Return `display_rows` with the mark of its rule color appended to each colored cell.
+    ///
+    /// Rules are matched against the unformatted cells of `rows`.
+    ///
+    fn mark_cells<'a>(
+        &'a self,
+        rules: &ColumnRules<'a>,
+        column_names: &[&str],
+        rows: &[Vec<String>],
+        display_rows: &[Vec<String>],
+    ) -> Vec<Vec<String>> {
+        rows.iter()
+            .zip(display_rows)
+            .enumerate()
+            .map(|(r, (row, display_row))| {
+                display_row
+                    .iter()
+                    .zip(row)
+                    .take(column_names.len())
+                    .enumerate()
+                    .map(
+                        |(i, (text, cell))| match self.rule_color_for(rules, r, i, cell) {
+                            Some(color) if text.is_empty() => color_mark(color).to_string(),
+                            Some(color) => format!("{} {}", text, color_mark(color)),
+                            None => text.clone(),
+                        },
+                    )
+                    .chain(display_row.iter().skip(column_names.len()).cloned())
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Return the rows with every `number_formats` pattern applied to its column.
+    fn format_numbers(&self, column_names: &[&str], rows: &[Vec<String>]) -> Vec<Vec<String>> {
+        let formats: Vec<Option<&NumberFormat>> = column_names
+            .iter()
+            .map(|name| self.number_formats.iter().find(|f| f.column == *name))
+            .collect();
+        rows.iter()
+            .map(|row| {
+                row.iter()
+                    .enumerate()
+                    .map(|(i, cell)| {
+                        let text = self.column_types.get(i) == Some(&Some(ColumnType::Text));
+                        let locale = self.locale.as_ref().filter(|_| !text);
+                        match formats.get(i).copied().flatten() {
+                            Some(format) => format.apply_in(cell, locale),
+                            None => locale.and_then(|locale| locale.localize(cell)),
+                        }
+                        .unwrap_or_else(|| cell.clone())
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Return the rows with the `prefixes` and `suffixes` of their columns added.
+    fn decorate_cells(&self, column_names: &[&str], rows: &[Vec<String>]) -> Vec<Vec<String>> {
+        let affix = |affixes: &[(String, String)], name: &str| -> String {
+            affixes
+                .iter()
+                .filter(|(column, _)| column == name)
+                .map(|(_, text)| text.as_str())
+                .collect()
+        };
+        let affixes: Vec<(String, String)> = column_names
+            .iter()
+            .map(|name| (affix(&self.prefixes, name), affix(&self.suffixes, name)))
+            .collect();
+        rows.iter()
+            .map(|row| {
+                row.iter()
+                    .enumerate()
+                    .map(|(i, cell)| match affixes.get(i) {
+                        Some((prefix, suffix)) if !cell.is_empty() => {
+                            format!("{}{}{}", prefix, cell, suffix)
+                        }
+                        _ => cell.clone(),
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Return the rows as they are shown, with numbers formatted and cells decorated
+    /// and marked as configured.
+    fn display_rows<'a, 'r>(
+        &'r self,
+        rules: &ColumnRules<'r>,
+        column_names: &[&str],
+        rows: &'a [Vec<String>],
+    ) -> Cow<'a, [Vec<String>]> {
+        let mut display_rows = Cow::Borrowed(rows);
+        if !self.number_formats.is_empty() || self.locale.is_some() {
+            display_rows = Cow::Owned(self.format_numbers(column_names, &display_rows));
+        }
+        if !self.prefixes.is_empty() || !self.suffixes.is_empty() {
+            display_rows = Cow::Owned(self.decorate_cells(column_names, &display_rows));
+        }
+        if self.marks {
+            display_rows = Cow::Owned(self.mark_cells(rules, column_names, rows, &display_rows));
+        }
+        display_rows
+    }
+
+    /// Return the final width of each column: measured, widened to fit the empty
+    /// message and banner, and padded to `pad_to`.
+    fn layout_widths(
+        &self,
+        header_texts: &[String],
+        rows: &[Vec<String>],
+        display_rows: &[Vec<String>],
+        measured: &[Vec<usize>],
+    ) -> Vec<usize> {
+        let mut column_widths = self.measure_columns(header_texts, display_rows, measured);
+        let empty_message = self.empty_message.as_deref().filter(|_| rows.is_empty());
+        let spanning_texts = empty_message.into_iter().chain(self.banner.as_deref());
+        if let Some(last) = column_widths.len().checked_sub(1) {
+            for text in spanning_texts {
+                let inner_width = match self.format {
+                    Format::Plain | Format::AlignedTsv => {
+                        rendered_width(&column_widths, self.format)
+                    }
+                    _ => rendered_width(&column_widths, Format::Table) - 4,
+                };
+                column_widths[last] += display_width(text).saturating_sub(inner_width);
+            }
+        }
+        if let (Some(target), Format::Table | Format::Plain) = (self.pad_to, self.format) {
+            pad_columns(&mut column_widths, target, self.format);
+        }
+        column_widths
+    }
+
+    /// Return the type of each column, detecting it from `rows` where none is declared.
+    fn resolve_types(&self, column_count: usize, rows: &[Vec<String>]) -> Vec<ColumnType> {
+        detect_column_types(column_count, rows)
+            .into_iter()
+            .enumerate()
+            .map(|(i, detected)| {
+                self.column_types
+                    .get(i)
+                    .copied()
+                    .flatten()
+                    .unwrap_or(detected)
+            })
+            .collect()
+    }
+
+    /// Return the alignment of each column's cells.
+    ///
+    /// An explicit column alignment wins, then the type-based alignment when
+    /// `auto_align` is set, then the global `align`.
+    ///
+    fn resolve_alignments(&self, column_count: usize, rows: &[Vec<String>]) -> Vec<Align> {
+        let types = if self.auto_align {
+            self.resolve_types(column_count, rows)
+        } else {
+            Vec::new()
+        };
+        (0..column_count)
+            .map(|i| {
+                self.column_alignments
+                    .get(i)
+                    .copied()
+                    .flatten()
+                    .or_else(|| match types.get(i) {
+                        Some(kind) if kind.is_numeric() => Some(Align::Right),
+                        Some(ColumnType::Boolean) => Some(Align::Center),
+                        _ => None,
+                    })
+                    .unwrap_or(self.align)
+            })
+            .collect()
+    }
+
+    /// Whether each of `column_count` columns draws its rows the same whatever the
+    /// other rows hold, so that a table with fixed widths can be drawn a row at a time.
+    ///
+    /// Stripes, heatmaps, group lines, per-row colors, detected alignments and the
+    /// empty message all depend on the table as a whole.
+    ///
+    fn draws_rows_alone(&self, column_count: usize) -> bool {
+        let aligned = |i: usize| {
+            self.column_alignments.get(i).copied().flatten().is_some()
+                || self.column_types.get(i).copied().flatten().is_some()
+        };
+        matches!(
+            self.format,
+            Format::Table | Format::Plain | Format::AlignedTsv
+        ) && (!self.auto_align || (0..column_count).all(aligned))
+            && self.stripe_color.is_none()
+            && self.heatmaps.is_empty()
+            && self.group_lines.is_none()
+            && self.row_colors.is_empty()
+            && self.cell_colors.is_empty()
+            && self.dimmed_rows.is_empty()
+            && self.empty_message.is_none()
+            && self.page_width.is_none()
+    }
+
+    /// Return the heatmap color of every cell, indexed by row and then by column.
+    fn heat_colors(&self, column_names: &[&str], rows: &[Vec<String>]) -> Vec<Vec<Option<String>>> {
+        let mut colors = vec![vec![None; column_names.len()]; rows.len()];
+        for (i, name) in column_names.iter().enumerate() {
+            if !self.heatmaps.iter().any(|column| column == name) {
+                continue;
+            }
+            let values: Vec<Option<f64>> = rows
+                .iter()
+                .map(|row| {
+                    row.get(i)
+                        .and_then(|cell| cell.trim().parse::<f64>().ok())
+                        .filter(|value| value.is_finite())
+                })
+                .collect();
+            let (min, max) = values
+                .iter()
+                .flatten()
+                .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), &value| {
+                    (min.min(value), max.max(value))
+                });
+            for (row_colors, value) in colors.iter_mut().zip(values) {
+                row_colors[i] = value.map(|value| gradient_color(value, min, max));
+            }
+        }
+        colors
+    }
+
+    /// Return the color of `cell` in row `row` of column `column`.
+    ///
+    /// `heat` is the cell's heatmap color, which gives way to the row, value and
+    /// threshold rules.
+    ///
+    fn cell_color_for<'a>(
+        &'a self,
+        rules: &ColumnRules<'a>,
+        row: usize,
+        column: usize,
+        cell: &str,
+        heat: Option<&'a str>,
+    ) -> Option<&'a str> {
+        let rule_color = if self.marks {
+            None
+        } else {
+            self.rule_color_for(rules, row, column, cell)
+        };
+        rule_color
+            .or(heat)
+            .or(self.stripe_color.as_deref().filter(|_| row % 2 == 1))
+            .or(self.alt_column_color.as_deref().filter(|_| column % 2 == 1))
+            .or(self.cell_color.as_deref())
+    }
+}
+
+/// The value and threshold coloring rules of each column, looked up by name once
+/// per table rather than for every cell.
+struct ColumnRules<'a> {
+    value_colors: Vec<Vec<&'a ValueColors>>,
+    color_scales: Vec<Vec<&'a ColorScale>>,
+}
+
+impl<'a> ColumnRules<'a> {
+    /// Collect the rules of `options` that apply to each of `column_names`.
+    fn new(options: &'a TableOptions, column_names: &[&str]) -> Self {
+        ColumnRules {
+            value_colors: column_names
+                .iter()
+                .map(|name| {
+                    options
+                        .value_colors
+                        .iter()
+                        .filter(|rule| rule.column == *name)
+                        .collect()
+                })
+                .collect(),
+            color_scales: column_names
+                .iter()
+                .map(|name| {
+                    options
+                        .color_scales
+                        .iter()
+                        .filter(|scale| scale.column == *name)
+                        .collect()
+                })
+                .collect(),
+        }
+    }
+
+    /// Return the color that a value or threshold rule of `column` gives `cell`, if any.
+    fn color_for(&self, column: usize, cell: &str) -> Option<&'a str> {
+        self.value_colors
+            .get(column)
+            .and_then(|rules| rules.iter().find_map(|rule| rule.color_for(cell)))
+            .or_else(|| {
+                self.color_scales
+                    .get(column)
+                    .and_then(|scales| scales.iter().find_map(|scale| scale.color_for(cell)))
+            })
+    }
+}
+
+/// Return the width of every cell of `rows` as measured by `width_of`, in the
+/// order of `spanned_cells`.
+fn measure_cells(
+    column_count: usize,
+    rows: &[Vec<String>],
+    width_of: fn(&str) -> usize,
+) -> Vec<Vec<usize>> {
+    rows.iter()
+        .map(|row| {
+            spanned_cells(row, column_count)
+                .into_iter()
+                .map(|(_, _, _, text)| width_of(text))
+                .collect()
+        })
+        .collect()
+}
+
+/// Return the widest cell of each of the first `column_count` columns, given the
+/// cell widths in `measured`.
+///
+/// Cells spanning several columns are left out; see `fit_spans`.
+///
+fn cell_widths(column_count: usize, rows: &[Vec<String>], measured: &[Vec<usize>]) -> Vec<usize> {
+    let mut widths = vec![0; column_count];
+    for (row, row_widths) in rows.iter().zip(measured) {
+        for ((_, column, span, _), &width) in
+            spanned_cells(row, column_count).iter().zip(row_widths)
+        {
+            if *span == 1 {
+                widths[*column] = widths[*column].max(width);
+            }
+        }
+    }
+    widths
+}
+
+/// Widen the last column of each spanning cell that does not fit its columns.
+///
+/// `separator` is the width taken by the divider between two columns.
+///
+fn fit_spans(column_widths: &mut [usize], rows: &[Vec<String>], separator: usize) {
+    for row in rows {
+        for (_, column, span, text) in spanned_cells(row, column_widths.len()) {
+            if span > 1 {
+                let columns = &mut column_widths[column..column + span];
+                let width = columns.iter().sum::<usize>() + separator * (span - 1);
+                columns[span - 1] += block_width(text).saturating_sub(width);
+            }
+        }
+    }
+}
+
+/// Split a `@spanN:text` directive into the number of columns and the text.
+fn parse_span(cell: &str) -> Option<(usize, &str)> {
+    let (count, text) = cell.strip_prefix("@span")?.split_once(':')?;
+    let count: usize = count.parse().ok()?;
+    (count > 1).then_some((count, text))
+}
+
+/// Return the index, first column, column count and text of each cell of `row`.
+///
+/// A cell starting with `@spanN:` covers `N` columns, so the cell after it starts
+/// `N` columns later. Spans are cut at the last column, and cells beyond it are
+/// left out.
+///
+fn spanned_cells(row: &[String], column_count: usize) -> Vec<(usize, usize, usize, &str)> {
+    let mut cells = Vec::with_capacity(row.len());
+    let mut column = 0;
+    for (index, cell) in row.iter().enumerate() {
+        if column >= column_count {
+            break;
+        }
+        let (span, text) = parse_span(cell).unwrap_or((1, cell));
+        let span = span.min(column_count - column);
+        cells.push((index, column, span, text));
+        column += span;
+    }
+    cells
+}
+
+/// Compute `measure_cells` over `jobs` chunks of rows in parallel and join the results.
+fn parallel_measure_cells(
+    column_count: usize,
+    rows: &[Vec<String>],
+    jobs: usize,
+    width_of: fn(&str) -> usize,
+) -> Vec<Vec<usize>> {
+    let chunk_size = rows.len().div_ceil(jobs).max(1);
+    std::thread::scope(|scope| {
+        let handles: Vec<_> = rows
+            .chunks(chunk_size)
+            .map(|chunk| scope.spawn(move || measure_cells(column_count, chunk, width_of)))
+            .collect();
+        handles
+            .into_iter()
+            .flat_map(|handle| handle.join().unwrap())
+            .collect()
+    })
+}
+
+/// Return the display width of a table with columns of `column_widths`, borders included.
+fn rendered_width(column_widths: &[usize], format: Format) -> usize {
+    let content: usize = column_widths.iter().sum();
+    match format {
+        Format::Plain => content + 2 * column_widths.len().saturating_sub(1),
+        Format::AlignedTsv => content + column_widths.len().saturating_sub(1),
+        Format::Table | Format::Latex | Format::Jira | Format::Json => {
+            content + 3 * column_widths.len() + 1
+        }
+    }
+}
+
+/// Widen the columns so the table is `target` display columns wide, if it is narrower.
+///
+/// The extra space is shared evenly, with the remainder going to the leftmost columns.
+///
+fn pad_columns(column_widths: &mut [usize], target: usize, format: Format) {
+    let extra = target.saturating_sub(rendered_width(column_widths, format));
+    let count = column_widths.len();
+    if count == 0 {
+        return;
+    }
+    for (i, width) in column_widths.iter_mut().enumerate() {
+        *width += extra / count + usize::from(i < extra % count);
+    }
+}
+
+/// Group the columns into pages no wider than `page_width`, each starting with
+/// the first `frozen` columns.
+///
+/// Every page holds at least one column beyond the frozen ones, even if that makes
+/// it wider than `page_width`.
+///
+fn paginate(
+    column_widths: &[usize],
+    frozen: usize,
+    page_width: usize,
+    format: Format,
+) -> Vec<Vec<usize>> {
+    let frozen = frozen.min(column_widths.len());
+    let width_of = |columns: &[usize]| {
+        let widths: Vec<usize> = columns.iter().map(|&i| column_widths[i]).collect();
+        rendered_width(&widths, format)
+    };
+    let mut pages = Vec::new();
+    let mut page: Vec<usize> = (0..frozen).collect();
+    for column in frozen..column_widths.len() {
+        page.push(column);
+        if page.len() > frozen + 1 && width_of(&page) > page_width {
+            page.pop();
+            pages.push(std::mem::replace(&mut page, (0..frozen).collect()));
+            page.push(column);
+        }
+    }
+    if page.len() > frozen || pages.is_empty() {
+        pages.push(page);
+    }
+    pages
+}
+
+/// Write one table per page of columns, separated by blank lines.
+///
+/// The note about omitted rows and the legend are only written after the last page.
+///
+fn write_pages(
+    column_names: &[&str],
+    rows: &[Vec<String>],
+    options: &TableOptions,
+    pages: &[Vec<usize>],
+    writer: &mut impl Write,
+) -> Result<(), TabbsError> {
+    for (p, page) in pages.iter().enumerate() {
+        let last = p + 1 == pages.len();
+        let names: Vec<&str> = page.iter().map(|&i| column_names[i]).collect();
+        let page_rows: Vec<Vec<String>> = rows
+            .iter()
+            .map(|row| {
+                page.iter()
+                    .map(|&i| row.get(i).cloned().unwrap_or_default())
+                    .collect()
+            })
+            .collect();
+        let page_options = TableOptions {
+            column_widths: page
+                .iter()
+                .map(|&i| options.column_widths.get(i).copied().flatten())
+                .collect(),
+            max_column_widths: page
+                .iter()
+                .map(|&i| options.max_column_widths.get(i).copied().flatten())
+                .collect(),
+            column_alignments: page
+                .iter()
+                .map(|&i| options.column_alignments.get(i).copied().flatten())
+                .collect(),
+            column_types: page
+                .iter()
+                .map(|&i| options.column_types.get(i).copied().flatten())
+                .collect(),
+            row_header: options.row_header && page[0] == 0,
+            legend: options.legend && last,
+            caption: options.caption.clone().filter(|_| last),
+            omitted_rows: if last { options.omitted_rows } else { 0 },
+            page_width: None,
+            ..options.clone()
+        };
+        if p > 0 {
+            writeln!(writer)?;
+        }
+        print_table_to_writer(&names, &page_rows, &page_options, writer)?;
+    }
+    Ok(())
+}
+
+/// Return `text` aligned in a cell of `width` display columns, optionally colored and bold.
+fn pad_cell(
+    text: &str,
+    width: usize,
+    align: Align,
+    color: Option<&str>,
+    bold: bool,
+    dim: bool,
+) -> String {
+    pad_measured(text, display_width(text), width, align, color, bold, dim)
+}
+
+/// Pad `text`, which is `text_width` display columns wide, like `pad_cell`.
+fn pad_measured(
+    text: &str,
+    text_width: usize,
+    width: usize,
+    align: Align,
+    color: Option<&str>,
+    bold: bool,
+    dim: bool,
+) -> String {
+    let padding = width.saturating_sub(text_width);
+    let (left, right) = match align {
+        Align::Left => (0, padding),
+        Align::Right => (padding, 0),
+        Align::Center => (padding / 2, padding - padding / 2),
+    };
+    let styled = paint(text, color, bold);
+    let styled = if dim && !text.is_empty() {
+        styled.dimmed().to_string()
+    } else {
+        styled
+    };
+    format!("{}{}{}", " ".repeat(left), styled, " ".repeat(right))
+}
+
+/// A cell to be written by `write_box_row` or `write_plain_row`.
+struct CellView<'a> {
+    text: &'a str,
+    /// The number of columns the cell covers.
+    span: usize,
+    /// The display width of the widest line of `text`, if it was measured while
+    /// sizing the columns.
+    text_width: Option<usize>,
+    align: Align,
+    color: Option<&'a str>,
+    bold: bool,
+    /// Show the text dimmed, on top of its color.
+    dim: bool,
+    /// The URL the cell text links to, shown as an OSC 8 hyperlink.
+    link: Option<&'a str>,
+    /// Cut the middle out of lines that do not fit, keeping this share for their start.
+    truncate_middle: Option<f64>,
+}
+
+impl CellView<'_> {
+    /// Return `line` of this cell, wrapped in a hyperlink when the cell has one.
+    fn line_text(&self, line: &str) -> String {
+        match self.link {
+            Some(url) if !line.is_empty() => hyperlink(url, line),
+            _ => line.to_string(),
+        }
+    }
+}
+
+/// Split every cell of a row into lines truncated to their column widths.
+///
+/// Returns the lines of each cell and the height of the tallest cell.
+///
+fn cell_lines(cells: &[CellView], cell_widths: &[usize]) -> (Vec<Vec<(String, usize)>>, usize) {
+    let lines: Vec<Vec<(String, usize)>> = cells
+        .iter()
+        .zip(cell_widths)
+        .map(|(cell, &width)| {
+            if let Some(text_width) = cell.text_width.filter(|&text_width| text_width <= width) {
+                // The cell is known to fit, so only its width needs to be known.
+                if !cell.text.contains('\n') {
+                    return vec![(cell.text.to_string(), text_width)];
+                }
+                return cell
+                    .text
+                    .split('\n')
+                    .map(|line| (line.to_string(), display_width(line)))
+                    .collect();
+            }
+            cell.text
+                .split('\n')
+                .map(|line| {
+                    let line_width = display_width(line);
+                    if line_width <= width {
+                        return (line.to_string(), line_width);
+                    }
+                    // Only trailing whitespace would be cut, so no `…` is needed.
+                    let trimmed = line.trim_end();
+                    let trimmed_width = display_width(trimmed);
+                    if trimmed_width <= width {
+                        return (trimmed.to_string(), trimmed_width);
+                    }
+                    let line = match cell.truncate_middle {
+                        Some(ratio) => truncate_middle(line, width, ratio),
+                        None => truncate_to_width(line, width),
+                    };
+                    let line_width = display_width(&line);
+                    (line, line_width)
+                })
+                .collect()
+        })
+        .collect();
+    let height = lines.iter().map(Vec::len).max().unwrap_or(1);
+    (lines, height)
+}
+
+/// Return the width of each cell, including the dividers it spans, and the last column it covers.
+///
+/// `separator` is the width taken by the divider between two columns.
+///
+fn span_widths(
+    cells: &[CellView],
+    column_widths: &[usize],
+    separator: usize,
+) -> (Vec<usize>, Vec<usize>) {
+    let mut column = 0;
+    cells
+        .iter()
+        .map(|cell| {
+            let columns = &column_widths[column..column + cell.span];
+            let width = columns.iter().sum::<usize>() + separator * (cell.span - 1);
+            column += cell.span;
+            (width, column - 1)
+        })
+        .unzip()
+}
+
+/// Write a row of cells between vertical borders, spanning as many lines as its tallest cell.
+///
+/// Each cell is followed by the character returned by `divider` for its column.
+///
+fn write_box_row(
+    writer: &mut impl Write,
+    cells: &[CellView],
+    column_widths: &[usize],
+    border: char,
+    divider: impl Fn(usize) -> char,
+) -> io::Result<()> {
+    let (widths, last_columns) = span_widths(cells, column_widths, 3);
+    let (lines, height) = cell_lines(cells, &widths);
+    for line in 0..height {
+        write!(writer, "{}", border)?;
+        for (i, cell) in cells.iter().enumerate() {
+            let (text, text_width) = lines[i]
+                .get(line)
+                .map_or(("", 0), |(s, w)| (s.as_str(), *w));
+            let padded = pad_measured(
+                &cell.line_text(text),
+                text_width,
+                widths[i],
+                cell.align,
+                cell.color,
+                cell.bold,
+                cell.dim,
+            );
+            write!(writer, " {} {}", padded, divider(last_columns[i]))?;
+        }
+        writeln!(writer)?;
+    }
+    Ok(())
+}
+
+/// Write a row of space-aligned cells, leaving a left-aligned last column unpadded.
+fn write_plain_row(
+    writer: &mut impl Write,
+    cells: &[CellView],
+    column_widths: &[usize],
+    gap: &str,
+) -> io::Result<()> {
+    let (widths, last_columns) = span_widths(cells, column_widths, gap.len());
+    let (lines, height) = cell_lines(cells, &widths);
+    for line in 0..height {
+        let texts: Vec<String> = cells
+            .iter()
+            .enumerate()
+            .map(|(i, cell)| {
+                let (text, text_width) = lines[i]
+                    .get(line)
+                    .map_or(("", 0), |(s, w)| (s.as_str(), *w));
+                let width =
+                    if last_columns[i] + 1 == column_widths.len() && cell.align == Align::Left {
+                        0
+                    } else {
+                        widths[i]
+                    };
+                pad_measured(
+                    &cell.line_text(text),
+                    text_width,
+                    width,
+                    cell.align,
+                    cell.color,
+                    cell.bold,
+                    cell.dim,
+                )
+            })
+            .collect();
+        writeln!(writer, "{}", texts.join(gap))?;
+    }
+    Ok(())
+}
+
+/// Print a table with the given column names, row data and rendering options to the provided writer.
+///
+/// Column names and cells may contain newlines, in which case their row spans
+/// several lines with each cell top-aligned.
+///
+/// # Arguments
+///
+/// * `column_names` - A slice of strings representing the column names.
+/// * `rows` - A slice of Vec<String> representing the rows of data.
+/// * `options` - The options controlling colors and column widths.
+/// * `writer` - A mutable reference to a writer implementing the `Write` trait.
+///
+/// # Errors
+///
+/// Returns `TabbsError::Io` if writing to `writer` fails.
+///
+pub fn print_table_to_writer(
+    column_names: &[&str],
+    rows: &[Vec<String>],
+    options: &TableOptions,
+    writer: &mut impl Write,
+) -> Result<(), TabbsError> {
+    if options.no_trailing_newline {
+        let options = TableOptions {
+            no_trailing_newline: false,
+            ..options.clone()
+        };
+        let mut output = Vec::new();
+        print_table_to_writer(column_names, rows, &options, &mut output)?;
+        if output.last() == Some(&b'\n') {
+            output.pop();
+        }
+        writer.write_all(&output)?;
+        return Ok(());
+    }
+    if options.format == Format::Json {
+        write_json_table(column_names, rows, options, writer)?;
+        return Ok(());
+    }
+    let rules = ColumnRules::new(options, column_names);
+    let display_rows = options.display_rows(&rules, column_names, rows);
+    let display_rows: &[Vec<String>] = &display_rows;
+    let header_texts = options.header_texts(column_names);
+    // Every rule and row below is drawn from these widths, so they must not change
+    // after this point.
+    let measured = options.measure_cells(column_names.len(), display_rows);
+    let column_widths = options.layout_widths(&header_texts, rows, display_rows, &measured);
+    let empty_message = options.empty_message.as_deref().filter(|_| rows.is_empty());
+    if let (Some(page_width), Format::Table | Format::Plain) = (options.page_width, options.format)
+    {
+        let pages = paginate(
+            &column_widths,
+            options.frozen_columns,
+            page_width,
+            options.format,
+        );
+        if pages.len() > 1 {
+            return write_pages(column_names, rows, options, &pages, writer);
+        }
+    }
+
+    let alignments = options.resolve_alignments(column_names.len(), rows);
+    if options.format == Format::Latex {
+        write_latex_table(
+            column_names,
+            display_rows,
+            &alignments,
+            options.omitted_rows,
+            writer,
+        )?;
+        return Ok(());
+    }
+    if options.format == Format::Jira {
+        write_jira_table(column_names, display_rows, options.omitted_rows, writer)?;
+        return Ok(());
+    }
+    let header: Vec<CellView> = header_texts
+        .iter()
+        .enumerate()
+        .map(|(i, text)| CellView {
+            text,
+            align: alignments[i],
+            span: 1,
+            text_width: None,
+            color: options.header_color.as_deref(),
+            bold: false,
+            dim: false,
+            link: None,
+            truncate_middle: options.truncate_middle,
+        })
+        .collect();
+    let heat_colors = if options.heatmaps.is_empty() {
+        Vec::new()
+    } else {
+        options.heat_colors(column_names, rows)
+    };
+    let body = rows
+        .iter()
+        .zip(display_rows)
+        .enumerate()
+        .map(|(r, (row, display_row))| {
+            spanned_cells(display_row, column_widths.len())
+                .into_iter()
+                .zip(&measured[r])
+                .map(|((index, i, span, text), &text_width)| {
+                    let cell = row.get(index).map_or("", String::as_str);
+                    CellView {
+                        text,
+                        span,
+                        text_width: Some(text_width).filter(|_| !options.rtrim_for_width),
+                        align: alignments[i],
+                        color: options.cell_color_for(
+                            &rules,
+                            r,
+                            i,
+                            cell,
+                            heat_colors.get(r).and_then(|row| row[i].as_deref()),
+                        ),
+                        bold: options.row_header && i == 0,
+                        dim: options.dimmed_rows.get(r).copied().unwrap_or(false),
+                        link: Some(cell.trim())
+                            .filter(|cell| options.hyperlinks && looks_like_url(cell)),
+                        truncate_middle: options.truncate_middle,
+                    }
+                })
+                .collect::<Vec<CellView>>()
+        });
+
+    if matches!(options.format, Format::Plain | Format::AlignedTsv) {
+        let gap = if options.format == Format::AlignedTsv {
+            "\t"
+        } else {
+            "  "
+        };
+        if let Some(banner) = &options.banner {
+            let width = rendered_width(&column_widths, options.format);
+            writeln!(
+                writer,
+                "{}",
+                pad_cell(banner, width, Align::Center, None, false, false).trim_end()
+            )?;
+        }
+        write_plain_row(writer, &header, &column_widths, gap)?;
+        for cells in body {
+            write_plain_row(writer, &cells, &column_widths, gap)?;
+        }
+        if let Some(message) = empty_message {
+            let width = rendered_width(&column_widths, options.format);
+            writeln!(
+                writer,
+                "{}",
+                pad_cell(message, width, Align::Center, None, false, false).trim_end()
+            )?;
+        }
+        if options.omitted_rows > 0 {
+            writeln!(writer, "{}", omitted_rows_note(options.omitted_rows))?;
+        }
+        if let Some(caption) = &options.caption {
+            let width = rendered_width(&column_widths, options.format);
+            write_caption(writer, caption, width, options.caption_align)?;
+        }
+        if options.legend {
+            write_legend(options, writer)?;
+        }
+        return Ok(());
+    }
+
+    let border = &options.border;
+    let rule = |left: char, junction: char, right: char| {
+        let junction = if options.outer_only {
+            border.horizontal
+        } else {
+            junction
+        };
+        let line: String = column_widths
+            .iter()
+            .map(|width| border.horizontal.to_string().repeat(width + 2))
+            .collect::<Vec<String>>()
+            .join(&junction.to_string());
+        format!("{}{}{}", left, line, right)
+    };
+    let divider = |i: usize| {
+        if options.row_header && i == 0 && column_widths.len() > 1 {
+            border.row_header_vertical
+        } else if options.outer_only && i + 1 < column_widths.len() {
+            ' '
+        } else {
+            border.vertical
+        }
+    };
+
+    if let Some(banner) = &options.banner {
+        writeln!(
+            writer,
+            "{}",
+            rule(border.top_left, border.horizontal, border.top_right)
+        )?;
+        write_spanning_row(
+            writer,
+            banner,
+            &column_widths,
+            border.vertical,
+            Align::Center,
+        )?;
+        writeln!(
+            writer,
+            "{}",
+            rule(
+                border.left_junction,
+                border.top_junction,
+                border.right_junction
+            )
+        )?;
+    } else {
+        writeln!(
+            writer,
+            "{}",
+            rule(border.top_left, border.top_junction, border.top_right)
+        )?;
+    }
+    write_box_row(writer, &header, &column_widths, border.vertical, divider)?;
+    if !options.outer_only && !options.no_header_rule {
+        let header_rule = rule(border.left_junction, border.junction, border.right_junction);
+        writeln!(writer, "{}", header_rule)?;
+    }
+
+    let group_column = options
+        .group_lines
+        .as_ref()
+        .and_then(|name| column_names.iter().position(|column| column == name));
+    for (r, cells) in body.enumerate() {
+        let new_group = group_column.is_some_and(|i| {
+            r > 0 && rows[r].get(i).map(String::as_str) != rows[r - 1].get(i).map(String::as_str)
+        });
+        if new_group {
+            let group_rule = rule(border.left_junction, border.junction, border.right_junction);
+            writeln!(writer, "{}", group_rule)?;
+        }
+        write_box_row(writer, &cells, &column_widths, border.vertical, divider)?;
+    }
+    if let Some(message) = empty_message {
+        write_spanning_row(
+            writer,
+            message,
+            &column_widths,
+            border.vertical,
+            Align::Center,
+        )?;
+    }
+    if options.omitted_rows > 0 {
+        let note = omitted_rows_note(options.omitted_rows);
+        write_spanning_row(writer, &note, &column_widths, border.vertical, Align::Left)?;
+    }
+
+    writeln!(
+        writer,
+        "{}",
+        rule(
+            border.bottom_left,
+            border.bottom_junction,
+            border.bottom_right
+        )
+    )?;
+    if let Some(caption) = &options.caption {
+        let width = rendered_width(&column_widths, options.format);
+        write_caption(writer, caption, width, options.caption_align)?;
+    }
+
+    if options.legend {
+        write_legend(options, writer)?;
+    }
+    Ok(())
+}
+
+/// Return the width in display columns of each column's content, as
+/// `print_table_to_writer` lays out the table for the same arguments.
+///
+/// Widths count the display width of wide and combining characters, fit the
+/// headers, formatted numbers and spans, and honor the fixed and maximum column
+/// widths and `pad_to`. Borders and cell padding are not included, and a table
+/// split into pages of columns is measured as a whole.
+///
+/// # Examples
+///
+/// ```
+/// use tabbs::{compute_column_widths, TableOptions};
+///
+/// let rows = vec![vec!["東京".to_string(), "35".to_string()]];
+/// let widths = compute_column_widths(&["city", "age"], &rows, &TableOptions::default());
+/// assert_eq!(widths, vec![4, 3]);
+/// ```
+///
+pub fn compute_column_widths(
+    column_names: &[&str],
+    rows: &[Vec<String>],
+    options: &TableOptions,
+) -> Vec<usize> {
+    let rules = ColumnRules::new(options, column_names);
+    let display_rows = options.display_rows(&rules, column_names, rows);
+    let header_texts = options.header_texts(column_names);
+    let measured = options.measure_cells(column_names.len(), &display_rows);
+    options.layout_widths(&header_texts, rows, &display_rows, &measured)
+}
+
+/// Return the width and height, in display columns and lines, of the table that
+/// `print_table_to_writer` would print for the same arguments.
+///
+/// Borders, wrapped headers, multi-line cells, captions and pages of columns are
+/// all accounted for; color escapes take up no width. Bordered and plain tables
+/// are measured from their column widths and the lines of each row, without
+/// drawing them; other formats and tables split into pages are drawn and measured.
+///
+/// # Examples
+///
+/// ```
+/// use tabbs::{table_dimensions, TableOptions};
+///
+/// let rows = vec![
+///     vec!["jack".to_string(), "35".to_string(), "neat".to_string()],
+///     vec!["jane".to_string(), "50".to_string(), "cool".to_string()],
+///     vec!["erin".to_string(), "20".to_string(), "nice".to_string()],
+/// ];
+/// let options = TableOptions::default();
+/// assert_eq!(table_dimensions(&["name", "age", "text"], &rows, &options), (21, 7));
+/// ```
+///
+pub fn table_dimensions(
+    column_names: &[&str],
+    rows: &[Vec<String>],
+    options: &TableOptions,
+) -> (usize, usize) {
+    let rules = ColumnRules::new(options, column_names);
+    let display_rows = options.display_rows(&rules, column_names, rows);
+    let header_texts = options.header_texts(column_names);
+    let measured = options.measure_cells(column_names.len(), &display_rows);
+    let column_widths = options.layout_widths(&header_texts, rows, &display_rows, &measured);
+    let pages = options.page_width.map_or(1, |page_width| {
+        let frozen = options.frozen_columns;
+        paginate(&column_widths, frozen, page_width, options.format).len()
+    });
+    if column_names.is_empty()
+        || pages > 1
+        || !matches!(options.format, Format::Table | Format::Plain)
+    {
+        let mut output = Vec::new();
+        print_table_to_writer(column_names, rows, options, &mut output)
+            .expect("writing to a Vec cannot fail");
+        return text_dimensions(&String::from_utf8_lossy(&output));
+    }
+
+    let table_width = rendered_width(&column_widths, options.format);
+    let alignments = options.resolve_alignments(column_names.len(), rows);
+    let header: Vec<(usize, usize, &str)> = header_texts
+        .iter()
+        .enumerate()
+        .map(|(i, text)| (i, 1, text.as_str()))
+        .collect();
+    let body: Vec<Vec<(usize, usize, &str)>> = display_rows
+        .iter()
+        .map(|row| {
+            spanned_cells(row, column_widths.len())
+                .into_iter()
+                .map(|(_, column, span, text)| (column, span, text))
+                .collect()
+        })
+        .collect();
+    let height_of = |cells: &[(usize, usize, &str)]| {
+        cells
+            .iter()
+            .map(|(_, _, text)| text.split('\n').count())
+            .max()
+            .unwrap_or(1)
+    };
+    let separator = if options.format == Format::Plain {
+        2
+    } else {
+        3
+    };
+    // A plain row leaves a left-aligned last column unpadded, and a short row of
+    // either format stops after its last cell.
+    let width_of = |cells: &[(usize, usize, &str)]| -> usize {
+        let widths = cells.iter().map(|&(column, span, text)| {
+            let columns = &column_widths[column..column + span];
+            let width = columns.iter().sum::<usize>() + separator * (span - 1);
+            let unpadded = options.format == Format::Plain
+                && column + span == column_widths.len()
+                && alignments[column] == Align::Left;
+            if unpadded {
+                block_width(text).min(width)
+            } else {
+                width
+            }
+        });
+        match options.format {
+            Format::Plain => widths.sum::<usize>() + separator * cells.len().saturating_sub(1),
+            _ => 1 + widths.map(|width| width + separator).sum::<usize>(),
+        }
+    };
+
+    let mut height = height_of(&header) + body.iter().map(|cells| height_of(cells)).sum::<usize>();
+    let mut width = body
+        .iter()
+        .map(|cells| width_of(cells))
+        .fold(width_of(&header), usize::max);
+    if options.format == Format::Table {
+        let group_column = options
+            .group_lines
+            .as_ref()
+            .and_then(|name| column_names.iter().position(|column| column == name));
+        let group_rules = group_column.map_or(0, |i| {
+            rows.windows(2)
+                .filter(|pair| pair[0].get(i) != pair[1].get(i))
+                .count()
+        });
+        let header_rule = !options.outer_only && !options.no_header_rule;
+        // The top and bottom borders, and the rule below the banner.
+        let banner_rule = options.banner.is_some();
+        height += 2 + usize::from(banner_rule) + usize::from(header_rule) + group_rules;
+        width = width.max(table_width);
+    }
+    // Notes, captions and the legend are short, so they are written out and measured.
+    let mut extra = Vec::new();
+    write_table_notes(options, &column_widths, rows.is_empty(), &mut extra)
+        .expect("writing to a Vec cannot fail");
+    let (extra_width, extra_height) = text_dimensions(&String::from_utf8_lossy(&extra));
+    (width.max(extra_width), height + extra_height)
+}
+
+/// Write the lines of a bordered or plain table that are not part of its header
+/// or rows: the banner, the empty message, the omitted rows note, the caption and
+/// the legend, as `print_table_to_writer` draws them.
+fn write_table_notes(
+    options: &TableOptions,
+    column_widths: &[usize],
+    empty: bool,
+    writer: &mut impl Write,
+) -> io::Result<()> {
+    let table_width = rendered_width(column_widths, options.format);
+    let notes = options
+        .banner
+        .iter()
+        .chain(options.empty_message.as_ref().filter(|_| empty));
+    let omitted =
+        Some(omitted_rows_note(options.omitted_rows)).filter(|_| options.omitted_rows > 0);
+    if options.format == Format::Plain {
+        for note in notes {
+            let line = pad_cell(note, table_width, Align::Center, None, false, false);
+            writeln!(writer, "{}", line.trim_end())?;
+        }
+        if let Some(note) = omitted {
+            writeln!(writer, "{}", note)?;
+        }
+    } else {
+        let border = options.border.vertical;
+        for note in notes {
+            write_spanning_row(writer, note, column_widths, border, Align::Center)?;
+        }
+        if let Some(note) = omitted {
+            write_spanning_row(writer, &note, column_widths, border, Align::Left)?;
+        }
+    }
+    if let Some(caption) = &options.caption {
+        write_caption(writer, caption, table_width, options.caption_align)?;
+    }
+    if options.legend {
+        write_legend(options, writer)?;
+    }
+    Ok(())
+}
+
+/// Return the display width of the widest line of `text` and its number of lines.
+fn text_dimensions(text: &str) -> (usize, usize) {
+    let width = text.lines().map(display_width).max().unwrap_or(0);
+    (width, text.lines().count())
+}
+
+/// A writer that hands every complete line to a callback, without its newline.
+struct LineCallback<F> {
+    line: Vec<u8>,
+    callback: F,
+}
+
+impl<F: FnMut(&str)> Write for LineCallback<F> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        for &byte in buf {
+            if byte == b'\n' {
+                (self.callback)(&String::from_utf8_lossy(&self.line));
+                self.line.clear();
+            } else {
+                self.line.push(byte);
+            }
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Render a table like `print_table_to_writer`, calling `callback` with each
+/// line instead of writing to a writer.
+///
+/// Lines are passed without their newline, so they can be routed into buffers,
+/// pagers or terminal UIs. `width_hints` fixes the width of the columns that
+/// have an entry; the others are sized from their content.
+///
+/// When every column has a fixed width and no option needs the whole table, such
+/// as stripes, heatmaps, group lines or per-row colors, the header is passed on
+/// at once and each row as soon as `rows` yields it. Otherwise every row is
+/// collected before the first line is drawn.
+///
+/// # Errors
+///
+/// Returns an error if the table cannot be rendered.
+///
+pub fn render_streaming<F: FnMut(&str)>(
+    column_names: &[&str],
+    width_hints: &[Option<usize>],
+    rows: impl IntoIterator<Item = Vec<String>>,
+    options: &TableOptions,
+    mut callback: F,
+) -> Result<(), TabbsError> {
+    let column_widths = (0..column_names.len())
+        .map(|i| {
+            width_hints
+                .get(i)
+                .copied()
+                .flatten()
+                .or_else(|| options.column_widths.get(i).copied().flatten())
+        })
+        .collect();
+    let options = TableOptions {
+        column_widths,
+        ..options.clone()
+    };
+    let fixed = options.column_widths.iter().all(Option::is_some);
+    if !fixed || !options.draws_rows_alone(column_names.len()) {
+        let rows: Vec<Vec<String>> = rows.into_iter().collect();
+        let mut writer = LineCallback {
+            line: Vec::new(),
+            callback,
+        };
+        print_table_to_writer(column_names, &rows, &options, &mut writer)?;
+        if !writer.line.is_empty() {
+            (writer.callback)(&String::from_utf8_lossy(&writer.line));
+        }
+        return Ok(());
+    }
+
+    // The lines of the empty table that differ from those of a table with one blank
+    // row split it into what comes above the rows and what comes below them.
+    let empty = table_lines(column_names, &[], &options)?;
+    let blank = table_lines(
+        column_names,
+        &[vec![String::new(); column_names.len()]],
+        &options,
+    )?;
+    let head = empty.iter().zip(&blank).take_while(|(a, b)| a == b).count();
+    let tail = empty.len() - head;
+    for line in &empty[..head] {
+        callback(line);
+    }
+    for row in rows {
+        let lines = table_lines(column_names, std::slice::from_ref(&row), &options)?;
+        for line in &lines[head..lines.len() - tail] {
+            callback(line);
+        }
+    }
+    for line in &empty[head..] {
+        callback(line);
+    }
+    Ok(())
+}
+
+/// Return the lines of the table `print_table_to_writer` draws, without their newlines.
+fn table_lines(
+    column_names: &[&str],
+    rows: &[Vec<String>],
+    options: &TableOptions,
+) -> Result<Vec<String>, TabbsError> {
+    let mut output = Vec::new();
+    print_table_to_writer(column_names, rows, options, &mut output)?;
+    if output.last() == Some(&b'\n') {
+        output.pop();
+    }
+    Ok(String::from_utf8_lossy(&output)
+        .split('\n')
+        .map(str::to_string)
+        .collect())
+}
+
+/// Print a table like `print_table_to_writer`, with every line moved `offset`
+/// columns to the right.
+///
+/// # Errors
+///
+/// Returns `TabbsError::Io` if writing to `writer` fails.
+///
+pub fn render_at(
+    offset: usize,
+    column_names: &[&str],
+    rows: &[Vec<String>],
+    options: &TableOptions,
+    writer: &mut impl Write,
+) -> Result<(), TabbsError> {
+    let mut output = Vec::new();
+    print_table_to_writer(column_names, rows, options, &mut output)?;
+    let indent = " ".repeat(offset);
+    for line in String::from_utf8_lossy(&output).lines() {
+        writeln!(writer, "{}{}", indent, line)?;
+    }
+    Ok(())
+}
+
+/// Place two rendered tables next to each other, separated by `gutter` spaces.
+///
+/// The lines of `left` are padded to the width of its widest line, and the
+/// shorter table is continued with blank lines. Color escapes take up no width.
+///
+/// # Examples
+///
+/// ```
+/// use tabbs::side_by_side;
+///
+/// assert_eq!(side_by_side("+-+\n|a|\n+-+\n", "b\n", 2), "+-+  b\n|a|\n+-+\n");
+/// ```
+///
+pub fn side_by_side(left: &str, right: &str, gutter: usize) -> String {
+    let left_lines: Vec<&str> = left.lines().collect();
+    let right_lines: Vec<&str> = right.lines().collect();
+    let left_width = left_lines
+        .iter()
+        .map(|line| display_width(line))
+        .max()
+        .unwrap_or(0);
+    let mut merged = String::new();
+    for i in 0..left_lines.len().max(right_lines.len()) {
+        let left_line = left_lines.get(i).copied().unwrap_or("");
+        let line = match right_lines.get(i) {
+            Some(right_line) => {
+                let padding = left_width - display_width(left_line) + gutter;
+                format!("{}{}{}", left_line, " ".repeat(padding), right_line)
+            }
+            None => left_line.to_string(),
+        };
+        merged.push_str(line.trim_end());
+        merged.push('\n');
+    }
+    merged
+}
+
+/// Write the rows as a JSON array of objects keyed by column name.
+///
+/// Cells are strings unless `json_typed` is set, in which case cells that read as
+/// numbers or booleans are written as such, except in columns declared as text.
+///
+fn write_json_table(
+    column_names: &[&str],
+    rows: &[Vec<String>],
+    options: &TableOptions,
+    writer: &mut impl Write,
+) -> io::Result<()> {
+    if rows.is_empty() {
+        writeln!(writer, "[]")?;
+        return Ok(());
+    }
+    let keys: Vec<String> = column_names.iter().map(|name| json_string(name)).collect();
+    writeln!(writer, "[")?;
+    for (r, row) in rows.iter().enumerate() {
+        // A spanning cell belongs to its first column and leaves the others empty.
+        let mut cells = vec![""; keys.len()];
+        for (_, column, _, text) in spanned_cells(row, keys.len()) {
+            cells[column] = text;
+        }
+        let fields: Vec<String> = keys
+            .iter()
+            .zip(cells)
+            .enumerate()
+            .map(|(i, (key, cell))| {
+                let text_column = options.column_types.get(i) == Some(&Some(ColumnType::Text));
+                let value = if options.json_typed && !text_column {
+                    json_value(cell)
+                } else {
+                    json_string(cell)
+                };
+                format!("{}: {}", key, value)
+            })
+            .collect();
+        let comma = if r + 1 < rows.len() { "," } else { "" };
+        writeln!(writer, "  {{{}}}{}", fields.join(", "), comma)?;
+    }
+    writeln!(writer, "]")?;
+    Ok(())
+}
+
+/// Return `cell` as a JSON number or boolean if it reads as one, or as a string.
+///
+/// Numbers are written as they appear in the cell. Those that are not valid JSON,
+/// such as `+5`, `.5` and `007`, or that do not survive a round trip through an
+/// `f64` stay strings, so no digits are lost.
+///
+fn json_value(cell: &str) -> String {
+    let value = cell.trim();
+    match detect_type(value) {
+        ColumnType::Integer | ColumnType::Float if is_json_number(value) => value.to_string(),
+        ColumnType::Boolean => {
+            parse_bool(value).map_or_else(|| json_string(cell), |b| b.to_string())
+        }
+        _ => json_string(cell),
+    }
+}
+
+/// Whether `value` follows the JSON number grammar and has at most 15
+/// significant digits, the most an `f64` is guaranteed to keep.
+fn is_json_number(value: &str) -> bool {
+    let digits = |part: &str| !part.is_empty() && part.bytes().all(|b| b.is_ascii_digit());
+    let unsigned = value.strip_prefix('-').unwrap_or(value);
+    let (mantissa, exponent) = match unsigned.split_once(['e', 'E']) {
+        Some((mantissa, exponent)) => (mantissa, Some(exponent)),
+        None => (unsigned, None),
+    };
+    let (whole, fraction) = match mantissa.split_once('.') {
+        Some((whole, fraction)) => (whole, Some(fraction)),
+        None => (mantissa, None),
+    };
+    let valid = digits(whole)
+        && (whole == "0" || !whole.starts_with('0'))
+        && fraction.is_none_or(digits)
+        && exponent
+            .is_none_or(|exponent| digits(exponent.strip_prefix(['+', '-']).unwrap_or(exponent)));
+    let significant = format!("{}{}", whole, fraction.unwrap_or(""));
+    let significant = significant.trim_start_matches('0').trim_end_matches('0');
+    valid && significant.len() <= 15 && value.parse::<f64>().is_ok_and(f64::is_finite)
+}
+
+/// Write a JSON array describing each column of the table that
+/// `print_table_to_writer` would print for the same arguments.
+///
+/// Every column has its `name`, detected or declared `type`, content `width`
+/// and `align`ment, and the smallest and largest number in it as `min` and
+/// `max`, which are `null` for columns without numbers.
+///
+/// # Errors
+///
+/// Returns `TabbsError::Io` if writing to `writer` fails.
+///
+pub fn write_column_metadata(
+    column_names: &[&str],
+    rows: &[Vec<String>],
+    options: &TableOptions,
+    writer: &mut impl Write,
+) -> Result<(), TabbsError> {
+    let types = options.resolve_types(column_names.len(), rows);
+    let widths = compute_column_widths(column_names, rows, options);
+    let alignments = options.resolve_alignments(column_names.len(), rows);
+    writeln!(writer, "[")?;
+    for (i, name) in column_names.iter().enumerate() {
+        let numbers: Vec<f64> = rows
+            .iter()
+            .filter_map(|row| row.get(i)?.trim().parse::<f64>().ok())
+            .filter(|number| number.is_finite())
+            .collect();
+        let bound = |pick: fn(f64, f64) -> f64| {
+            numbers
+                .iter()
+                .copied()
+                .reduce(pick)
+                .map_or_else(|| "null".to_string(), |number| number.to_string())
+        };
+        let align = match alignments[i] {
+            Align::Left => "left",
+            Align::Right => "right",
+            Align::Center => "center",
+        };
+        let separator = if i + 1 < column_names.len() { "," } else { "" };
+        writeln!(
+            writer,
+            "  {{\"name\": {}, \"type\": \"{}\", \"width\": {}, \"align\": \"{}\", \"min\": {}, \"max\": {}}}{}",
+            json_string(name),
+            types[i],
+            widths[i],
+            align,
+            bound(f64::min),
+            bound(f64::max),
+            separator
+        )?;
+    }
+    writeln!(writer, "]")?;
+    Ok(())
+}
+
+/// Quote `text` as a JSON string, escaping quotes, backslashes and control characters.
+fn json_string(text: &str) -> String {
+    let mut quoted = String::with_capacity(text.len() + 2);
+    quoted.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if c.is_control() => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Write the table as Confluence/Jira wiki markup.
+fn write_jira_table(
+    column_names: &[&str],
+    rows: &[Vec<String>],
+    omitted_rows: usize,
+    writer: &mut impl Write,
+) -> io::Result<()> {
+    write_jira_row(writer, column_names.iter().copied(), "||")?;
+    for row in rows {
+        // Jira has no spanning cells, so a spanning cell is followed by blank ones.
+        let cells =
+            spanned_cells(row, column_names.len())
+                .into_iter()
+                .flat_map(|(_, _, span, text)| {
+                    std::iter::once(text).chain(std::iter::repeat_n(" ", span - 1))
+                });
+        write_jira_row(writer, cells, "|")?;
+    }
+    if omitted_rows > 0 {
+        writeln!(writer, "{}", omitted_rows_note(omitted_rows))?;
+    }
+    Ok(())
+}
+
+/// Write one row of wiki markup with `separator` around each escaped cell.
+fn write_jira_row<'a>(
+    writer: &mut impl Write,
+    cells: impl Iterator<Item = &'a str>,
+    separator: &str,
+) -> io::Result<()> {
+    let cells: Vec<String> = cells.map(jira_escape).collect();
+    writeln!(
+        writer,
+        "{}{}{}",
+        separator,
+        cells.join(separator),
+        separator
+    )?;
+    Ok(())
+}
+
+/// Escape the characters wiki markup treats specially in table cells.
+///
+/// Empty cells become a single space, since wiki markup collapses `||`.
+///
+fn jira_escape(text: &str) -> String {
+    if text.trim().is_empty() {
+        return " ".to_string();
+    }
+    text.replace('|', "\\|").replace('\n', " \\\\ ")
+}
+
+/// Write the table as a LaTeX `tabular` environment with `\hline` rules.
+fn write_latex_table(
+    column_names: &[&str],
+    rows: &[Vec<String>],
+    alignments: &[Align],
+    omitted_rows: usize,
+    writer: &mut impl Write,
+) -> io::Result<()> {
+    let spec: String = alignments
+        .iter()
+        .map(|align| match align {
+            Align::Left => 'l',
+            Align::Right => 'r',
+            Align::Center => 'c',
+        })
+        .collect();
+    writeln!(writer, "\\begin{{tabular}}{{{}}}", spec)?;
+    writeln!(writer, "\\hline")?;
+    write_latex_row(writer, column_names.iter().copied())?;
+    writeln!(writer, "\\hline")?;
+    for row in rows {
+        let cells: Vec<String> = spanned_cells(row, column_names.len())
+            .into_iter()
+            .map(|(_, column, span, text)| {
+                let text = latex_escape(text);
+                match span {
+                    1 => text,
+                    _ => format!(
+                        "\\multicolumn{{{}}}{{{}}}{{{}}}",
+                        span,
+                        &spec[column..=column],
+                        text
+                    ),
+                }
+            })
+            .collect();
+        writeln!(writer, "{} \\\\", cells.join(" & "))?;
+    }
+    if omitted_rows > 0 {
+        let note = latex_escape(&omitted_rows_note(omitted_rows));
+        writeln!(
+            writer,
+            "\\multicolumn{{{}}}{{l}}{{{}}} \\\\",
+            column_names.len(),
+            note
+        )?;
+    }
+    writeln!(writer, "\\hline")?;
+    writeln!(writer, "\\end{{tabular}}")?;
+    Ok(())
+}
+
+/// Write one row of a LaTeX `tabular`, escaping each cell.
+fn write_latex_row<'a>(
+    writer: &mut impl Write,
+    cells: impl Iterator<Item = &'a str>,
+) -> io::Result<()> {
+    let cells: Vec<String> = cells.map(latex_escape).collect();
+    writeln!(writer, "{} \\\\", cells.join(" & "))?;
+    Ok(())
+}
+
+/// Escape the characters LaTeX treats specially, joining multi-line text with spaces.
+fn latex_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' | '%' | '$' | '#' | '_' | '{' | '}' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            '\\' => escaped.push_str("\\textbackslash{}"),
+            '~' => escaped.push_str("\\textasciitilde{}"),
+            '^' => escaped.push_str("\\textasciicircum{}"),
+            '\n' => escaped.push(' '),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Return the note shown in place of `count` rows left out of the table.
+fn omitted_rows_note(count: usize) -> String {
+    let noun = if count == 1 { "row" } else { "rows" };
+    format!("… ({} more {})", count, noun)
+}
+
+/// Write `text` as a single cell spanning every column between the outer borders.
+///
+/// Nothing is written for a table without columns, which has no room for it.
+///
+fn write_spanning_row(
+    writer: &mut impl Write,
+    text: &str,
+    column_widths: &[usize],
+    border: char,
+    align: Align,
+) -> io::Result<()> {
+    if column_widths.is_empty() {
+        return Ok(());
+    }
+    let inner_width = column_widths.iter().map(|width| width + 3).sum::<usize>() - 3;
+    let text = truncate_to_width(text, inner_width);
+    let padded = pad_cell(&text, inner_width, align, None, false, false);
+    writeln!(writer, "{} {} {}", border, padded, border)?;
+    Ok(())
+}
+
+/// Write `caption` wrapped to `width` display columns, each line aligned within that width.
+fn write_caption(
+    writer: &mut impl Write,
+    caption: &str,
+    width: usize,
+    align: Align,
+) -> io::Result<()> {
+    for line in wrap_to_width(caption, width).split('\n') {
+        let padded = pad_cell(line, width, align, None, false, false);
+        writeln!(writer, "{}", padded.trim_end())?;
+    }
+    Ok(())
+}
+
+/// Write a key listing the color and condition of every active color rule.
+fn write_legend(options: &TableOptions, writer: &mut impl Write) -> io::Result<()> {
+    let entries: Vec<(&str, String)> = options
+        .value_colors
+        .iter()
+        .flat_map(|rule| rule.legend_entries())
+        .chain(
+            options
+                .color_scales
+                .iter()
+                .flat_map(|scale| scale.legend_entries()),
+        )
+        .collect();
+    if entries.is_empty() {
+        return Ok(());
+    }
+    let name_width = entries
+        .iter()
+        .map(|(color, _)| display_width(color))
+        .max()
+        .unwrap_or(0);
+    writeln!(writer, "Legend:")?;
+    for (color, condition) in entries {
+        let padding = " ".repeat(name_width - display_width(color));
+        if options.marks {
+            writeln!(
+                writer,
+                "  {} {}{} = {}",
+                color_mark(color),
+                color,
+                padding,
+                condition
+            )?;
+            continue;
+        }
+        writeln!(
+            writer,
+            "  {}{} = {}",
+            paint(color, Some(color), false),
+            padding,
+            condition
+        )?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::{parse_color_scale, parse_value_colors};
+    use colored::Colorize;
+    use std::io::Write;
+
+    #[test]
+    fn test_print_table_to_writer() {
+        let column_names = ["name", "age", "text"];
+        let rows = [
+            vec!["jack".to_string(), "35".to_string(), "neat".to_string()],
+            vec!["jane".to_string(), "50".to_string(), "cool".to_string()],
+            vec!["erin".to_string(), "20".to_string(), "nice".to_string()],
+        ];
+
+        let expected_output = "\
++------+-----+------+
+| name | age | text |
++------+-----+------+
+| jack | 35  | neat |
+| jane | 50  | cool |
+| erin | 20  | nice |
++------+-----+------+";
+        let mut output = Vec::new();
+
+        {
+            let mut output_writer = std::io::BufWriter::new(output.by_ref());
+            print_table_to_writer(
+                &column_names,
+                &rows,
+                &TableOptions::default(),
+                &mut output_writer,
+            )
+            .unwrap();
+        }
+        let output_str = String::from_utf8(output).unwrap();
+        assert_eq!(output_str.trim(), expected_output);
+    }
+
+    #[test]
+    fn test_color_scale_cells() {
+        colored::control::set_override(true);
+        let column_names = ["host", "cpu"];
+        let rows = [
+            vec!["a".to_string(), "30".to_string()],
+            vec!["b".to_string(), "70".to_string()],
+            vec!["c".to_string(), "90".to_string()],
+        ];
+        let options = TableOptions {
+            color_scales: vec![parse_color_scale("cpu:green<50,yellow<80,red").unwrap()],
+            ..TableOptions::default()
+        };
+        let mut output = Vec::new();
+        print_table_to_writer(&column_names, &rows, &options, &mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains(&format!("| a    | {}  |", "30".green())));
+        assert!(output.contains(&format!("| b    | {}  |", "70".yellow())));
+        assert!(output.contains(&format!("| c    | {}  |", "90".red())));
+    }
+
+    #[test]
+    fn test_legend() {
+        colored::control::set_override(true);
+        let options = TableOptions {
+            color_scales: vec![parse_color_scale("cpu:green<50,yellow<80,red").unwrap()],
+            legend: true,
+            ..TableOptions::default()
+        };
+        let mut output = Vec::new();
+        print_table_to_writer(&["cpu"], &[vec!["30".to_string()]], &options, &mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        let legend = output.split_once("+-----+\nLegend:\n").unwrap().1;
+        assert_eq!(
+            legend,
+            format!(
+                "  {}  = cpu < 50\n  {} = 50 <= cpu < 80\n  {}    = cpu >= 80\n",
+                "green".green(),
+                "yellow".yellow(),
+                "red".red()
+            )
+        );
+    }
+
+    #[test]
+    fn test_parallel_cell_widths() {
+        let rows: Vec<Vec<String>> = (0..10_000)
+            .map(|i| {
+                vec![
+                    "x".repeat(i % 97),
+                    "日".repeat(i % 13),
+                    format!("\x1b[1m{}\x1b[0m", i),
+                ]
+            })
+            .collect();
+        let sequential = measure_cells(3, &rows, block_width);
+        assert_eq!(cell_widths(3, &rows, &sequential), vec![96, 24, 4]);
+        for jobs in [2, 3, 8, 64] {
+            assert_eq!(
+                parallel_measure_cells(3, &rows, jobs, block_width),
+                sequential
+            );
+        }
+        assert!(parallel_measure_cells(3, &[], 4, block_width).is_empty());
+    }
+
+    #[test]
+    fn test_plain_format() {
+        let column_names = ["name", "age", "text"];
+        let rows = [
+            vec!["jack".to_string(), "35".to_string(), "neat".to_string()],
+            vec!["jane".to_string(), "50".to_string(), "cool".to_string()],
+            vec!["erin".to_string(), "20".to_string(), "ah".to_string()],
+        ];
+        let options = TableOptions {
+            format: Format::Plain,
+            ..TableOptions::default()
+        };
+        let mut output = Vec::new();
+        print_table_to_writer(&column_names, &rows, &options, &mut output).unwrap();
+        let expected_output = "\
+name  age  text
+jack  35   neat
+jane  50   cool
+erin  20   ah
+";
+        assert_eq!(String::from_utf8(output).unwrap(), expected_output);
+    }
+
+    #[test]
+    fn test_aligned_tsv_format() {
+        let column_names = ["name", "age", "text"];
+        let rows = [
+            vec!["jack".to_string(), "135".to_string(), "neat".to_string()],
+            vec!["jo".to_string(), "5".to_string(), "ok".to_string()],
+        ];
+        let options = TableOptions {
+            format: Format::AlignedTsv,
+            column_alignments: vec![None, Some(Align::Right)],
+            ..TableOptions::default()
+        };
+        let mut output = Vec::new();
+        print_table_to_writer(&column_names, &rows, &options, &mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert_eq!(output, "name\tage\ttext\njack\t135\tneat\njo  \t  5\tok\n");
+        for line in output.lines() {
+            let fields: Vec<&str> = line.split('\t').map(str::trim).collect();
+            assert_eq!(fields.len(), 3);
+        }
+    }
+
+    #[test]
+    fn test_column_alignments() {
+        let column_names = ["name", "age", "pct"];
+        let rows = [
+            vec!["jack".to_string(), "35".to_string(), "5".to_string()],
+            vec!["jane".to_string(), "100".to_string(), "50".to_string()],
+        ];
+        let options = TableOptions {
+            align: Align::Center,
+            column_alignments: vec![Some(Align::Left), Some(Align::Right)],
+            ..TableOptions::default()
+        };
+        let mut output = Vec::new();
+        print_table_to_writer(&column_names, &rows, &options, &mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("| jack |  35 |  5  |"));
+        assert!(output.contains("| jane | 100 | 50  |"));
+    }
+
+    #[test]
+    fn test_outer_only() {
+        let column_names = ["name", "age", "text"];
+        let rows = [
+            vec!["jack".to_string(), "35".to_string(), "neat".to_string()],
+            vec!["jane".to_string(), "50".to_string(), "cool".to_string()],
+        ];
+        let options = TableOptions {
+            outer_only: true,
+            ..TableOptions::default()
+        };
+        let mut output = Vec::new();
+        print_table_to_writer(&column_names, &rows, &options, &mut output).unwrap();
+        let expected_output = "\
++-------------------+
+| name   age   text |
+| jack   35    neat |
+| jane   50    cool |
++-------------------+
+";
+        assert_eq!(String::from_utf8(output).unwrap(), expected_output);
+    }
+
+    #[test]
+    fn test_multiline_header() {
+        let column_names = ["first\nname", "age"];
+        let rows = [
+            vec!["jack".to_string(), "35".to_string()],
+            vec!["jane".to_string(), "50".to_string()],
+        ];
+        let mut output = Vec::new();
+        print_table_to_writer(&column_names, &rows, &TableOptions::default(), &mut output).unwrap();
+        let expected_output = "\
++-------+-----+
+| first | age |
+| name  |     |
++-------+-----+
+| jack  | 35  |
+| jane  | 50  |
++-------+-----+
+";
+        assert_eq!(String::from_utf8(output).unwrap(), expected_output);
+    }
+
+    #[test]
+    fn test_omitted_rows_note() {
+        let column_names = ["number", "square"];
+        let mut rows: Vec<Vec<String>> = (1..=10)
+            .map(|i: u32| vec![i.to_string(), (i * i).to_string()])
+            .collect();
+        let options = TableOptions {
+            omitted_rows: crate::transform::limit_rows(&mut rows, 3),
+            ..TableOptions::default()
+        };
+        let mut output = Vec::new();
+        print_table_to_writer(&column_names, &rows, &options, &mut output).unwrap();
+        let expected_output = "\
++--------+--------+
+| number | square |
++--------+--------+
+| 1      | 1      |
+| 2      | 4      |
+| 3      | 9      |
+| … (7 more rows) |
++--------+--------+
+";
+        assert_eq!(String::from_utf8(output).unwrap(), expected_output);
+    }
+
+    #[test]
+    fn test_value_colors_cells() {
+        colored::control::set_override(true);
+        let column_names = ["job", "status"];
+        let rows = [
+            vec!["build".to_string(), "OK".to_string()],
+            vec!["test".to_string(), "FAIL".to_string()],
+            vec!["lint".to_string(), "SKIP".to_string()],
+        ];
+        let options = TableOptions {
+            value_colors: vec![
+                crate::color::parse_value_colors("status:OK=green,FAIL=red").unwrap()
+            ],
+            ..TableOptions::default()
+        };
+        let mut output = Vec::new();
+        print_table_to_writer(&column_names, &rows, &options, &mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains(&format!("| build | {}     |", "OK".green())));
+        assert!(output.contains(&format!("| test  | {}   |", "FAIL".red())));
+        assert!(output.contains("| lint  | SKIP   |"));
+    }
+
+    #[test]
+    fn test_latex_format() {
+        let column_names = vec!["file_name", "size"];
+        let rows = vec![vec!["my_notes.txt".to_string(), "12".to_string()]];
+        let options = TableOptions {
+            format: Format::Latex,
+            column_alignments: vec![None, Some(Align::Right)],
+            ..TableOptions::default()
+        };
+        let mut output = Vec::new();
+        print_table_to_writer(&column_names, &rows, &options, &mut output).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "\\begin{tabular}{lr}\n\\hline\nfile\\_name & size \\\\\n\\hline\n\
+             my\\_notes.txt & 12 \\\\\n\\hline\n\\end{tabular}\n"
+        );
+        assert_eq!(
+            latex_escape("50% & ~$5"),
+            "50\\% \\& \\textasciitilde{}\\$5"
+        );
+    }
+
+    #[test]
+    fn test_no_header_rule() {
+        let rows = vec![vec!["jack".to_string(), "35".to_string()]];
+        let options = TableOptions {
+            no_header_rule: true,
+            ..TableOptions::default()
+        };
+        let mut output = Vec::new();
+        print_table_to_writer(&["name", "age"], &rows, &options, &mut output).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "+------+-----+\n| name | age |\n| jack | 35  |\n+------+-----+\n"
+        );
+    }
+
+    #[test]
+    fn test_number_formats() {
+        let rows = vec![
+            vec!["tea".to_string(), "3.5".to_string()],
+            vec!["cake".to_string(), "12".to_string()],
+        ];
+        let options = TableOptions {
+            number_formats: crate::number::parse_number_formats("price:${:.2}").unwrap(),
+            ..TableOptions::default()
+        };
+        let mut output = Vec::new();
+        print_table_to_writer(&["item", "price"], &rows, &options, &mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("| tea  | $3.50  |"));
+        assert!(output.contains("| cake | $12.00 |"));
+    }
+
+    #[test]
+    fn test_row_colors() {
+        colored::control::set_override(true);
+        let rows = [
+            vec!["+".to_string(), "finn".to_string()],
+            vec!["".to_string(), "erin".to_string()],
+        ];
+        let options = TableOptions {
+            cell_color: Some("blue".to_string()),
+            row_colors: vec![Some("green".to_string())],
+            cell_colors: vec![Vec::new(), vec![None, Some("yellow".to_string())]],
+            ..TableOptions::default()
+        };
+        let mut output = Vec::new();
+        print_table_to_writer(&["status", "name"], &rows, &options, &mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains(&format!("| {}      | {} |", "+".green(), "finn".green())));
+        assert!(output.contains(&format!("| {}       | {} |", "".blue(), "erin".yellow())));
+    }
+
+    #[test]
+    fn test_header_wrap() {
+        let rows = vec![vec!["12".to_string(), "a very long cell".to_string()]];
+        let options = TableOptions {
+            header_wrap: Some(8),
+            ..TableOptions::default()
+        };
+        let mut output = Vec::new();
+        print_table_to_writer(
+            &["number of requests", "note"],
+            &rows,
+            &options,
+            &mut output,
+        )
+        .unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "+----------+------------------+\n\
+             | number   | note             |\n\
+             | of       |                  |\n\
+             | requests |                  |\n\
+             +----------+------------------+\n\
+             | 12       | a very long cell |\n\
+             +----------+------------------+\n"
+        );
+    }
+
+    #[test]
+    fn test_unicode_border() {
+        let rows = vec![vec!["jack".to_string(), "35".to_string()]];
+        let options = TableOptions {
+            border: "unicode".parse().unwrap(),
+            ..TableOptions::default()
+        };
+        let mut output = Vec::new();
+        print_table_to_writer(&["name", "age"], &rows, &options, &mut output).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "┌──────┬─────┐\n\
+             │ name │ age │\n\
+             ├──────┼─────┤\n\
+             │ jack │ 35  │\n\
+             └──────┴─────┘\n"
+        );
+        assert!(BorderChars::ASCII.is_ascii() && !BorderChars::UNICODE.is_ascii());
+        assert!("double".parse::<BorderChars>().is_err());
+    }
+
+    #[test]
+    fn test_row_header() {
+        colored::control::set_override(true);
+        let rows = vec![vec!["cpu".to_string(), "42".to_string()]];
+        let options = TableOptions {
+            row_header: true,
+            ..TableOptions::default()
+        };
+        let mut output = Vec::new();
+        print_table_to_writer(&["host", "load"], &rows, &options, &mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("| host # load |"));
+        assert!(output.contains(&format!("| {}  # 42   |", "cpu".bold())));
+    }
+
+    #[test]
+    fn test_hidden_headers() {
+        let rows = vec![vec!["jack".to_string(), "35".to_string()]];
+        let options = TableOptions {
+            hidden_headers: vec!["name".to_string()],
+            ..TableOptions::default()
+        };
+        let mut output = Vec::new();
+        print_table_to_writer(&["name", "age"], &rows, &options, &mut output).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "+------+-----+\n|      | age |\n+------+-----+\n| jack | 35  |\n+------+-----+\n"
+        );
+    }
+
+    #[test]
+    fn test_frozen_columns() {
+        let rows = vec![vec![
+            "jack".to_string(),
+            "35".to_string(),
+            "neat".to_string(),
+        ]];
+        let options = TableOptions {
+            page_width: Some(16),
+            frozen_columns: 1,
+            ..TableOptions::default()
+        };
+        let mut output = Vec::new();
+        print_table_to_writer(&["name", "age", "text"], &rows, &options, &mut output).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "+------+-----+\n| name | age |\n+------+-----+\n| jack | 35  |\n+------+-----+\n\
+             \n\
+             +------+------+\n| name | text |\n+------+------+\n| jack | neat |\n+------+------+\n"
+        );
+    }
+
+    #[test]
+    fn test_hyperlinks() {
+        let rows = vec![vec!["docs".to_string(), "https://a.io".to_string()]];
+        let options = TableOptions {
+            hyperlinks: true,
+            ..TableOptions::default()
+        };
+        let mut output = Vec::new();
+        print_table_to_writer(&["name", "url"], &rows, &options, &mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        let link = hyperlink("https://a.io", "https://a.io");
+        assert!(output.contains(&format!("| docs | {} |\n", link)));
+        assert!(output.starts_with("+------+--------------+\n"));
+    }
+
+    #[test]
+    fn test_header_alignment() {
+        let rows = vec![
+            vec!["jack".to_string(), "1500".to_string()],
+            vec!["jane".to_string(), "35".to_string()],
+        ];
+        let options = TableOptions {
+            auto_align: true,
+            ..TableOptions::default()
+        };
+        let mut output = Vec::new();
+        print_table_to_writer(&["name", "n"], &rows, &options, &mut output).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "+------+------+\n| name |    n |\n+------+------+\n\
+             | jack | 1500 |\n| jane |   35 |\n+------+------+\n"
+        );
+    }
+
+    #[test]
+    fn test_rounded_border_junctions() {
+        let rows = vec![
+            vec!["jack".to_string(), "35".to_string(), "neat".to_string()],
+            vec!["jane".to_string(), "50".to_string(), "cool".to_string()],
+        ];
+        let options = TableOptions {
+            border: "rounded".parse().unwrap(),
+            ..TableOptions::default()
+        };
+        let mut output = Vec::new();
+        print_table_to_writer(&["name", "age", "text"], &rows, &options, &mut output).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "╭──────┬─────┬──────╮\n\
+             │ name │ age │ text │\n\
+             ├──────┼─────┼──────┤\n\
+             │ jack │ 35  │ neat │\n\
+             │ jane │ 50  │ cool │\n\
+             ╰──────┴─────┴──────╯\n"
+        );
+    }
+
+    #[test]
+    fn test_caption() {
+        let rows = vec![vec!["jack".to_string(), "35".to_string()]];
+        let options = TableOptions {
+            caption: Some("Source: x".to_string()),
+            caption_align: Align::Center,
+            ..TableOptions::default()
+        };
+        let mut output = Vec::new();
+        print_table_to_writer(&["name", "age"], &rows, &options, &mut output).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "+------+-----+\n| name | age |\n+------+-----+\n| jack | 35  |\n+------+-----+\n\
+             \x20 Source: x\n"
+        );
+
+        let options = TableOptions {
+            caption: Some("Data from the 2020 census".to_string()),
+            ..TableOptions::default()
+        };
+        let mut output = Vec::new();
+        print_table_to_writer(&["name", "age"], &rows, &options, &mut output).unwrap();
+        assert!(String::from_utf8(output)
+            .unwrap()
+            .ends_with("+------+-----+\nData from the\n2020 census\n"));
+    }
+
+    #[test]
+    fn test_heavy_theme_and_stripes() {
+        colored::control::set_override(true);
+        let theme: Theme = "heavy".parse().unwrap();
+        assert_eq!(theme.border, BorderChars::HEAVY);
+        assert_eq!(theme.header_color, Some("yellow"));
+        assert!("loud".parse::<Theme>().is_err());
+
+        let rows = vec![
+            vec!["jack".to_string()],
+            vec!["jane".to_string()],
+            vec!["erin".to_string()],
+        ];
+        let options = TableOptions {
+            border: theme.border,
+            header_color: theme.header_color.map(str::to_string),
+            stripe_color: Some("blue".to_string()),
+            ..TableOptions::default()
+        };
+        let mut output = Vec::new();
+        print_table_to_writer(&["name"], &rows, &options, &mut output).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            format!(
+                "┏━━━━━━┓\n┃ {} ┃\n┣━━━━━━┫\n┃ jack ┃\n┃ {} ┃\n┃ erin ┃\n┗━━━━━━┛\n",
+                "name".yellow(),
+                "jane".blue()
+            )
+        );
+    }
+
+    #[test]
+    fn test_jira_format() {
+        let rows = vec![
+            vec!["jack".to_string(), "a|b".to_string()],
+            vec!["jane".to_string(), String::new()],
+        ];
+        let options = TableOptions {
+            format: Format::Jira,
+            ..TableOptions::default()
+        };
+        let mut output = Vec::new();
+        print_table_to_writer(&["name", "text"], &rows, &options, &mut output).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "||name||text||\n|jack|a\\|b|\n|jane| |\n"
+        );
+    }
+
+    #[test]
+    fn test_locale_numbers() {
+        let rows = vec![vec!["tea".to_string(), "1234.5".to_string()]];
+        let options = TableOptions {
+            locale: Some("de".parse().unwrap()),
+            ..TableOptions::default()
+        };
+        let mut output = Vec::new();
+        print_table_to_writer(&["item", "price"], &rows, &options, &mut output).unwrap();
+        assert!(String::from_utf8(output)
+            .unwrap()
+            .contains("| tea  | 1.234,5 |\n"));
+    }
+
+    #[test]
+    fn test_empty_message() {
+        let options = TableOptions {
+            empty_message: Some("no data".to_string()),
+            ..TableOptions::default()
+        };
+        let mut output = Vec::new();
+        print_table_to_writer(&["name", "age", "text"], &[], &options, &mut output).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "+------+-----+------+\n| name | age | text |\n+------+-----+------+\n\
+             |      no data      |\n+------+-----+------+\n"
+        );
+
+        let mut output = Vec::new();
+        print_table_to_writer(&["id"], &[], &options, &mut output).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "+---------+\n| id      |\n+---------+\n| no data |\n+---------+\n"
+        );
+
+        let mut output = Vec::new();
+        print_table_to_writer(&[], &[], &options, &mut output).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), "++\n|\n++\n++\n");
+    }
+
+    #[test]
+    fn test_spanning_cells() {
+        let rows = vec![
+            vec![
+                "tea".to_string(),
+                "2".to_string(),
+                "3".to_string(),
+                "6".to_string(),
+            ],
+            vec!["@span3:Grand total".to_string(), "6".to_string()],
+        ];
+        let options = TableOptions::default();
+        let mut output = Vec::new();
+        print_table_to_writer(
+            &["item", "qty", "price", "sum"],
+            &rows,
+            &options,
+            &mut output,
+        )
+        .unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "+------+-----+-------+-----+\n\
+             | item | qty | price | sum |\n\
+             +------+-----+-------+-----+\n\
+             | tea  | 2   | 3     | 6   |\n\
+             | Grand total        | 6   |\n\
+             +------+-----+-------+-----+\n"
+        );
+        assert_eq!(parse_span("@span3:Total"), Some((3, "Total")));
+        assert_eq!(parse_span("@span1:Total"), None);
+        assert_eq!(parse_span("@spanx:Total"), None);
+    }
+
+    #[test]
+    fn test_spanning_cells_formats() {
+        let rows = vec![vec!["@span2:Total".to_string(), "3".to_string()]];
+        let render = |format| {
+            let options = TableOptions {
+                format,
+                ..TableOptions::default()
+            };
+            let mut output = Vec::new();
+            print_table_to_writer(&["x", "y", "z"], &rows, &options, &mut output).unwrap();
+            String::from_utf8(output).unwrap()
+        };
+        assert!(render(Format::Latex).contains("\n\\multicolumn{2}{l}{Total} & 3 \\\\\n"));
+        assert!(render(Format::Jira).ends_with("\n|Total| |3|\n"));
+        assert!(render(Format::Json).contains("{\"x\": \"Total\", \"y\": \"\", \"z\": \"3\"}"));
+    }
+
+    #[test]
+    fn test_json_typed() {
+        let rows = vec![
+            vec!["jack".to_string(), "35".to_string(), "yes".to_string()],
+            vec!["jane \"j\"".to_string(), "1.5".to_string(), "".to_string()],
+        ];
+        let mut options = TableOptions {
+            format: Format::Json,
+            ..TableOptions::default()
+        };
+        let mut output = Vec::new();
+        print_table_to_writer(&["name", "age", "ok"], &rows, &options, &mut output).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "[\n  {\"name\": \"jack\", \"age\": \"35\", \"ok\": \"yes\"},\n  \
+             {\"name\": \"jane \\\"j\\\"\", \"age\": \"1.5\", \"ok\": \"\"}\n]\n"
+        );
+
+        options.json_typed = true;
+        let mut output = Vec::new();
+        print_table_to_writer(&["name", "age", "ok"], &rows, &options, &mut output).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "[\n  {\"name\": \"jack\", \"age\": 35, \"ok\": true},\n  \
+             {\"name\": \"jane \\\"j\\\"\", \"age\": 1.5, \"ok\": \"\"}\n]\n"
+        );
+    }
+
+    #[test]
+    fn test_json_value() {
+        assert_eq!(json_value(" 35 "), "35");
+        assert_eq!(json_value("-1.50"), "-1.50");
+        assert_eq!(json_value("2.5e3"), "2.5e3");
+        assert_eq!(
+            json_value("1000000000000000000000"),
+            "1000000000000000000000"
+        );
+        assert_eq!(json_value("007"), "\"007\"");
+        assert_eq!(json_value("+5"), "\"+5\"");
+        assert_eq!(json_value(".5"), "\".5\"");
+        assert_eq!(
+            json_value("12345678901234567890"),
+            "\"12345678901234567890\""
+        );
+        assert_eq!(json_value("0.1234567890123456"), "\"0.1234567890123456\"");
+    }
+
+    #[test]
+    fn test_alt_column_color() {
+        let rows = vec![vec![
+            "jack".to_string(),
+            "35".to_string(),
+            "neat".to_string(),
+            "x".to_string(),
+        ]];
+        let options = TableOptions {
+            format: Format::Plain,
+            alt_column_color: Some("blue".to_string()),
+            ..TableOptions::default()
+        };
+        let mut output = Vec::new();
+        print_table_to_writer(&["name", "age", "text", "n"], &rows, &options, &mut output).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            format!(
+                "name  age  text  n\njack  {}   neat  {}\n",
+                "35".blue(),
+                "x".blue()
+            )
+        );
+    }
+
+    #[test]
+    fn test_pad_to() {
+        let rows = vec![vec!["jack".to_string(), "35".to_string()]];
+        let options = TableOptions {
+            pad_to: Some(20),
+            ..TableOptions::default()
+        };
+        let mut output = Vec::new();
+        print_table_to_writer(&["name", "age"], &rows, &options, &mut output).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "+---------+--------+\n\
+             | name    | age    |\n\
+             +---------+--------+\n\
+             | jack    | 35     |\n\
+             +---------+--------+\n"
+        );
+    }
+
+    #[test]
+    fn test_table_dimensions() {
+        let rows = vec![
+            vec!["jack".to_string(), "multi\nline".to_string()],
+            vec!["jane".to_string(), "ok".to_string()],
+        ];
+        let options = TableOptions {
+            caption: Some("source: tests".to_string()),
+            ..TableOptions::default()
+        };
+        assert_eq!(
+            table_dimensions(&["name", "text"], &rows, &options),
+            (16, 8)
+        );
+        let plain = TableOptions {
+            format: Format::Plain,
+            ..TableOptions::default()
+        };
+        assert_eq!(table_dimensions(&["name", "text"], &rows, &plain), (11, 4));
+    }
+
+    #[test]
+    fn test_table_dimensions_match_output() {
+        let rows = [
+            vec![
+                "jack".to_string(),
+                "multi\nline".to_string(),
+                "x".to_string(),
+            ],
+            vec!["@span2:spanning total".to_string(), "7".to_string()],
+            vec!["jack".to_string()],
+            vec![
+                "jane".to_string(),
+                "a very long cell".to_string(),
+                "".to_string(),
+            ],
+        ];
+        let names = ["name", "text", "long header"];
+        let variants: Vec<TableOptions> = vec![
+            TableOptions::default(),
+            TableOptions {
+                banner: Some("A banner wider than the whole table".to_string()),
+                caption: Some("a caption that wraps over more than one line".to_string()),
+                ..TableOptions::default()
+            },
+            TableOptions {
+                group_lines: Some("name".to_string()),
+                omitted_rows: 3,
+                header_wrap: Some(4),
+                ..TableOptions::default()
+            },
+            TableOptions {
+                column_widths: vec![Some(2), Some(30), None],
+                column_alignments: vec![None, None, Some(Align::Right)],
+                ..TableOptions::default()
+            },
+            TableOptions {
+                outer_only: true,
+                pad_to: Some(60),
+                legend: true,
+                value_colors: vec![parse_value_colors("name:jack=red").unwrap()],
+                ..TableOptions::default()
+            },
+            TableOptions {
+                no_header_rule: true,
+                max_column_widths: vec![None, Some(3), Some(1)],
+                ..TableOptions::default()
+            },
+        ];
+        for options in variants {
+            for format in [Format::Table, Format::Plain] {
+                let options = TableOptions {
+                    format,
+                    ..options.clone()
+                };
+                for rows in [&rows[..], &[]] {
+                    let mut output = Vec::new();
+                    print_table_to_writer(&names, rows, &options, &mut output).unwrap();
+                    let output = String::from_utf8(output).unwrap();
+                    let expected = (
+                        output.lines().map(display_width).max().unwrap_or(0),
+                        output.lines().count(),
+                    );
+                    assert_eq!(
+                        table_dimensions(&names, rows, &options),
+                        expected,
+                        "{:?}\n{}",
+                        options,
+                        output
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_marks() {
+        let rows = [
+            vec!["a".to_string(), "30".to_string()],
+            vec!["b".to_string(), "70".to_string()],
+            vec!["c".to_string(), "90".to_string()],
+        ];
+        let options = TableOptions {
+            color_scales: vec![parse_color_scale("cpu:green<50,yellow<80,red").unwrap()],
+            marks: true,
+            legend: true,
+            ..TableOptions::default()
+        };
+        let mut output = Vec::new();
+        print_table_to_writer(&["host", "cpu"], &rows, &options, &mut output).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "+------+--------+\n\
+             | host | cpu    |\n\
+             +------+--------+\n\
+             | a    | 30 (+) |\n\
+             | b    | 70 (~) |\n\
+             | c    | 90 (!) |\n\
+             +------+--------+\n\
+             Legend:\n  \
+             (+) green  = cpu < 50\n  \
+             (~) yellow = 50 <= cpu < 80\n  \
+             (!) red    = cpu >= 80\n"
+        );
+    }
+
+    #[test]
+    fn test_banner() {
+        let rows = vec![vec!["jack".to_string(), "35".to_string()]];
+        let options = TableOptions {
+            border: BorderChars::UNICODE,
+            banner: Some("people".to_string()),
+            ..TableOptions::default()
+        };
+        let mut output = Vec::new();
+        print_table_to_writer(&["name", "age"], &rows, &options, &mut output).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "┌────────────┐\n\
+             │   people   │\n\
+             ├──────┬─────┤\n\
+             │ name │ age │\n\
+             ├──────┼─────┤\n\
+             │ jack │ 35  │\n\
+             └──────┴─────┘\n"
+        );
+
+        let mut output = Vec::new();
+        print_table_to_writer(&[], &[], &options, &mut output).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), "┌┐\n├┤\n│\n├┤\n└┘\n");
+    }
+
+    #[test]
+    fn test_dimmed_rows() {
+        colored::control::set_override(true);
+        let rows = vec![
+            vec!["jack".to_string(), "active".to_string()],
+            vec!["jane".to_string(), "inactive".to_string()],
+        ];
+        let options = TableOptions {
+            format: Format::Plain,
+            cell_color: Some("red".to_string()),
+            dimmed_rows: vec![false, true],
+            ..TableOptions::default()
+        };
+        let mut output = Vec::new();
+        print_table_to_writer(&["name", "status"], &rows, &options, &mut output).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            format!(
+                "name  status\n{}  {}\n{}  {}\n",
+                "jack".red(),
+                "active".red(),
+                "jane".red().to_string().dimmed(),
+                "inactive".red().to_string().dimmed()
+            )
+        );
+    }
+
+    #[test]
+    fn test_rtrim_for_width() {
+        let rows = vec![
+            vec!["jack      ".to_string(), "35".to_string()],
+            vec!["jane".to_string(), "50".to_string()],
+        ];
+        let options = TableOptions {
+            rtrim_for_width: true,
+            ..TableOptions::default()
+        };
+        let mut output = Vec::new();
+        print_table_to_writer(&["name", "age"], &rows, &options, &mut output).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "+------+-----+\n\
+             | name | age |\n\
+             +------+-----+\n\
+             | jack | 35  |\n\
+             | jane | 50  |\n\
+             +------+-----+\n"
+        );
+    }
+
+    #[test]
+    fn test_group_lines() {
+        let rows = vec![
+            vec!["red".to_string(), "jack".to_string()],
+            vec!["red".to_string(), "jane".to_string()],
+            vec!["blue".to_string(), "erin".to_string()],
+        ];
+        let options = TableOptions {
+            group_lines: Some("team".to_string()),
+            ..TableOptions::default()
+        };
+        let mut output = Vec::new();
+        print_table_to_writer(&["team", "name"], &rows, &options, &mut output).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "+------+------+\n\
+             | team | name |\n\
+             +------+------+\n\
+             | red  | jack |\n\
+             | red  | jane |\n\
+             +------+------+\n\
+             | blue | erin |\n\
+             +------+------+\n"
+        );
+    }
+
+    #[test]
+    fn test_side_by_side() {
+        let render = |names: &[&str], rows: &[Vec<String>]| {
+            let mut output = Vec::new();
+            render_at(1, names, rows, &TableOptions::default(), &mut output).unwrap();
+            String::from_utf8(output).unwrap()
+        };
+        let left = render(
+            &["name"],
+            &[vec!["jack".to_string()], vec!["jane".to_string()]],
+        );
+        let right = render(&["age"], &[vec!["35".to_string()]]);
+        assert_eq!(
+            side_by_side(&left, &right, 2),
+            " +------+   +-----+\n \
+              | name |   | age |\n \
+              +------+   +-----+\n \
+              | jack |   | 35  |\n \
+              | jane |   +-----+\n \
+              +------+\n"
+        );
+    }
+
+    #[test]
+    fn test_max_column_widths() {
+        let rows = vec![vec![
+            "jack".to_string(),
+            "a long note".to_string(),
+            "ok".to_string(),
+        ]];
+        let options = TableOptions {
+            max_column_widths: vec![Some(10), None, Some(30)],
+            ..TableOptions::default()
+        };
+        let mut output = Vec::new();
+        print_table_to_writer(&["name", "note", "status"], &rows, &options, &mut output).unwrap();
+        assert!(String::from_utf8(output)
+            .unwrap()
+            .contains("| jack | a long note | ok     |"));
+
+        let options = TableOptions {
+            max_column_widths: vec![None, Some(6)],
+            ..TableOptions::default()
+        };
+        let mut output = Vec::new();
+        print_table_to_writer(&["name", "note", "status"], &rows, &options, &mut output).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "+------+--------+--------+\n\
+             | name | note   | status |\n\
+             +------+--------+--------+\n\
+             | jack | a lon… | ok     |\n\
+             +------+--------+--------+\n"
+        );
+    }
+
+    #[test]
+    fn test_no_trailing_newline() {
+        let options = TableOptions {
+            format: Format::Plain,
+            no_trailing_newline: true,
+            ..TableOptions::default()
+        };
+        let mut output = Vec::new();
+        print_table_to_writer(
+            &["name"],
+            &[vec!["jack".to_string()]],
+            &options,
+            &mut output,
+        )
+        .unwrap();
+        assert_eq!(output, b"name\njack");
+    }
+
+    #[test]
+    fn test_auto_align_by_type() {
+        let rows = vec![
+            vec!["jack".to_string(), "35".to_string(), "yes".to_string()],
+            vec!["jane".to_string(), "1500".to_string(), "no".to_string()],
+        ];
+        let mut options = TableOptions {
+            auto_align: true,
+            ..TableOptions::default()
+        };
+        let mut output = Vec::new();
+        print_table_to_writer(&["name", "n", "active"], &rows, &options, &mut output).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "+------+------+--------+\n\
+             | name |    n | active |\n\
+             +------+------+--------+\n\
+             | jack |   35 |  yes   |\n\
+             | jane | 1500 |   no   |\n\
+             +------+------+--------+\n"
+        );
+
+        options.column_alignments = vec![None, Some(Align::Left), Some(Align::Right)];
+        let mut output = Vec::new();
+        print_table_to_writer(&["name", "n", "active"], &rows, &options, &mut output).unwrap();
+        assert!(String::from_utf8(output)
+            .unwrap()
+            .contains("| jack | 35   |    yes |"));
+    }
+
+    #[test]
+    fn test_render_streaming() {
+        let rows = vec![
+            vec!["jack".to_string(), "35".to_string()],
+            vec!["jane".to_string(), "50".to_string()],
+        ];
+        let options = TableOptions::default();
+        let mut lines = Vec::new();
+        render_streaming(&["name", "age"], &[], rows.clone(), &options, |line| {
+            lines.push(line.to_string())
+        })
+        .unwrap();
+        let mut output = Vec::new();
+        print_table_to_writer(&["name", "age"], &rows, &options, &mut output).unwrap();
+        let expected: Vec<String> = String::from_utf8(output)
+            .unwrap()
+            .lines()
+            .map(str::to_string)
+            .collect();
+        assert_eq!(lines, expected);
+
+        let mut lines = Vec::new();
+        render_streaming(
+            &["name", "age"],
+            &[Some(6)],
+            rows.clone(),
+            &options,
+            |line| lines.push(line.to_string()),
+        )
+        .unwrap();
+        assert_eq!(lines[3], "| jack   | 35  |");
+
+        // With every width fixed, lines are passed on before the next row is pulled.
+        let pulled = std::cell::Cell::new(0);
+        let pulling = rows.iter().map(|row| {
+            pulled.set(pulled.get() + 1);
+            row.clone()
+        });
+        let mut seen = Vec::new();
+        render_streaming(
+            &["name", "age"],
+            &[Some(4), Some(3)],
+            pulling,
+            &options,
+            |line| seen.push((pulled.get(), line.to_string())),
+        )
+        .unwrap();
+        let pulls: Vec<usize> = seen.iter().map(|(pulled, _)| *pulled).collect();
+        assert_eq!(pulls, vec![0, 0, 0, 1, 2, 2]);
+        let lines: Vec<String> = seen.into_iter().map(|(_, line)| line).collect();
+        assert_eq!(lines, expected);
+    }
+
+    #[test]
+    fn test_prefixes_and_suffixes() {
+        let rows = vec![
+            vec!["tea".to_string(), "3.50".to_string(), "20".to_string()],
+            vec!["cake".to_string(), "12".to_string(), String::new()],
+        ];
+        let options = TableOptions {
+            auto_align: true,
+            prefixes: vec![("price".to_string(), "$".to_string())],
+            suffixes: vec![("pct".to_string(), "%".to_string())],
+            ..TableOptions::default()
+        };
+        let mut output = Vec::new();
+        print_table_to_writer(&["item", "price", "pct"], &rows, &options, &mut output).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "+------+-------+-----+\n\
+             | item | price | pct |\n\
+             +------+-------+-----+\n\
+             | tea  | $3.50 | 20% |\n\
+             | cake |   $12 |     |\n\
+             +------+-------+-----+\n"
+        );
+    }
+
+    #[test]
+    fn test_heatmap() {
+        colored::control::set_override(true);
+        let rows = vec![
+            vec!["a".to_string(), "40".to_string()],
+            vec!["b".to_string(), "10".to_string()],
+            vec!["c".to_string(), "90".to_string()],
+            vec!["d".to_string(), "n/a".to_string()],
+        ];
+        let options = TableOptions {
+            heatmaps: vec!["cpu".to_string()],
+            ..TableOptions::default()
+        };
+        let mut output = Vec::new();
+        print_table_to_writer(&["host", "cpu"], &rows, &options, &mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("| b    | \x1b[38;2;0;0;255m10\x1b[0m  |"));
+        assert!(output.contains("| c    | \x1b[38;2;255;0;0m90\x1b[0m  |"));
+        assert!(output.contains("| d    | n/a |"));
+    }
+
+    #[test]
+    fn test_compute_column_widths() {
+        let rows = vec![
+            vec![
+                "日本語".to_string(),
+                "café".to_string(),
+                "a long note".to_string(),
+            ],
+            vec!["x".to_string(), "🙂".to_string(), "short".to_string()],
+        ];
+        let names = ["name", "word", "note"];
+        assert_eq!(
+            compute_column_widths(&names, &rows, &TableOptions::default()),
+            vec![6, 4, 11]
+        );
+        let options = TableOptions {
+            column_widths: vec![Some(2)],
+            max_column_widths: vec![None, Some(3), Some(5)],
+            ..TableOptions::default()
+        };
+        assert_eq!(
+            compute_column_widths(&names, &rows, &options),
+            vec![2, 3, 5]
+        );
+    }
+
+    #[test]
+    fn test_capped_column_rules() {
+        let rows = vec![
+            vec!["日本語テキスト".to_string(), "a very long note".to_string()],
+            vec!["jane".to_string(), "short".to_string()],
+        ];
+        let options = TableOptions {
+            border: "unicode".parse().unwrap(),
+            max_column_widths: vec![Some(5), Some(8)],
+            banner: Some("people".to_string()),
+            ..TableOptions::default()
+        };
+        let mut output = Vec::new();
+        print_table_to_writer(&["name", "note"], &rows, &options, &mut output).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "┌──────────────────┐\n\
+             │      people      │\n\
+             ├───────┬──────────┤\n\
+             │ name  │ note     │\n\
+             ├───────┼──────────┤\n\
+             │ 日本… │ a very … │\n\
+             │ jane  │ short    │\n\
+             └───────┴──────────┘\n"
+        );
+    }
+
+    #[test]
+    fn test_write_column_metadata() {
+        let rows = vec![
+            vec!["jack".to_string(), "35".to_string(), "neat".to_string()],
+            vec!["jane".to_string(), "50".to_string(), "cool".to_string()],
+            vec!["erin".to_string(), "20".to_string(), "nice".to_string()],
+        ];
+        let options = TableOptions {
+            auto_align: true,
+            ..TableOptions::default()
+        };
+        let mut output = Vec::new();
+        write_column_metadata(&["name", "age", "text"], &rows, &options, &mut output).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "[\n  \
+             {\"name\": \"name\", \"type\": \"str\", \"width\": 4, \"align\": \"left\", \"min\": null, \"max\": null},\n  \
+             {\"name\": \"age\", \"type\": \"int\", \"width\": 3, \"align\": \"right\", \"min\": 20, \"max\": 50},\n  \
+             {\"name\": \"text\", \"type\": \"str\", \"width\": 4, \"align\": \"left\", \"min\": null, \"max\": null}\n\
+             ]\n"
+        );
+    }
+
+    #[test]
+    fn test_cached_cell_widths() {
+        let texts = [
+            "jack",
+            "日本語",
+            "two\nlines here",
+            "trailing   ",
+            "much too long",
+            "",
+        ];
+        let cell = |text: &'static str, text_width: Option<usize>| CellView {
+            text,
+            span: 1,
+            text_width,
+            align: Align::Left,
+            color: None,
+            bold: false,
+            dim: false,
+            link: None,
+            truncate_middle: None,
+        };
+        let cached: Vec<CellView> = texts
+            .iter()
+            .map(|text| cell(text, Some(block_width(text))))
+            .collect();
+        let measured: Vec<CellView> = texts.iter().map(|text| cell(text, None)).collect();
+        for width in [0, 3, 8, 12] {
+            let widths = vec![width; texts.len()];
+            assert_eq!(cell_lines(&cached, &widths), cell_lines(&measured, &widths));
+        }
+        let (lines, height) = cell_lines(&cached, &[4, 6, 10, 8, 6, 0]);
+        assert_eq!(height, 2);
+        assert_eq!(lines[1], vec![("日本語".to_string(), 6)]);
+        assert_eq!(lines[3], vec![("trailing".to_string(), 8)]);
+        assert_eq!(lines[4], vec![("much …".to_string(), 6)]);
+    }
+
+    #[test]
+    fn test_column_rules_large_table() {
+        let rows: Vec<Vec<String>> = (0..5000)
+            .map(|i| vec![format!("host{}", i), (i % 100).to_string()])
+            .collect();
+        let options = TableOptions {
+            color_scales: vec![
+                parse_color_scale("cpu:green<50,red").unwrap(),
+                parse_color_scale("other:yellow<1,red").unwrap(),
+            ],
+            marks: true,
+            ..TableOptions::default()
+        };
+        let rules = ColumnRules::new(&options, &["host", "cpu"]);
+        assert!(rules.color_scales[0].is_empty());
+        assert_eq!(rules.color_scales[1].len(), 1);
+        assert_eq!(rules.color_for(0, "10"), None);
+
+        let mut output = Vec::new();
+        print_table_to_writer(&["host", "cpu"], &rows, &options, &mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines.len(), rows.len() + 4);
+        assert_eq!(lines[3], "| host0    | 0 (+)  |");
+        assert_eq!(lines[3 + 4999], "| host4999 | 99 (!) |");
+        assert_eq!(output.matches("(+)").count(), 2500);
+        assert_eq!(output.matches("(!)").count(), 2500);
+    }
+}
@@ -0,0 +1,287 @@
+//! Helpers for measuring and fitting text to terminal columns.
+
+/// Return the number of terminal columns `s` occupies.
+///
+/// ANSI escape sequences, including OSC 8 hyperlinks, take no space and East
+/// Asian wide characters take two.
+///
+pub fn display_width(s: &str) -> usize {
+    let mut width = 0;
+    let mut chars = s.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' && chars.peek() == Some(&']') {
+            // Skip an OSC sequence up to its BEL or `ESC \\` terminator.
+            while let Some(c) = chars.next() {
+                if c == '\x07' || (c == '\x1b' && chars.next_if_eq(&'\\').is_some()) {
+                    break;
+                }
+            }
+        } else if c == '\x1b' {
+            // Skip a CSI sequence up to and including its final byte.
+            for c in chars.by_ref() {
+                if ('@'..='~').contains(&c) && c != '[' {
+                    break;
+                }
+            }
+        } else {
+            width += char_width(c);
+        }
+    }
+    width
+}
+
+/// Remove ANSI escape sequences and control characters from `s`, keeping line breaks.
+///
+/// Tabs become spaces, so that text from untrusted input cannot move the cursor,
+/// recolor the terminal or otherwise break the table layout.
+///
+pub fn sanitize(s: &str) -> String {
+    let mut clean = String::with_capacity(s.len());
+    let mut chars = s.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\x1b' if chars.next_if_eq(&']').is_some() => {
+                while let Some(c) = chars.next() {
+                    if c == '\x07' || (c == '\x1b' && chars.next_if_eq(&'\\').is_some()) {
+                        break;
+                    }
+                }
+            }
+            '\x1b' if chars.next_if_eq(&'[').is_some() => {
+                for c in chars.by_ref() {
+                    if ('@'..='~').contains(&c) {
+                        break;
+                    }
+                }
+            }
+            '\x1b' => {
+                chars.next();
+            }
+            '\n' => clean.push('\n'),
+            '\t' => clean.push(' '),
+            c if c.is_control() => {}
+            c => clean.push(c),
+        }
+    }
+    clean
+}
+
+/// Whether `s` is an `http://` or `https://` URL that can be shown as a hyperlink.
+pub fn looks_like_url(s: &str) -> bool {
+    let s = s.trim();
+    (s.starts_with("http://") || s.starts_with("https://")) && !s.contains(char::is_whitespace)
+}
+
+/// Wrap `text` in an OSC 8 escape sequence linking it to `url`.
+///
+/// Terminals that support OSC 8 show `text` as a clickable link to `url`, and
+/// `display_width` counts only `text`.
+///
+pub fn hyperlink(url: &str, text: &str) -> String {
+    format!("\x1b]8;;{}\x1b\\{}\x1b]8;;\x1b\\", url, text)
+}
+
+/// Return the display width of the widest line of `s`.
+pub fn block_width(s: &str) -> usize {
+    s.split('\n').map(display_width).max().unwrap_or(0)
+}
+
+/// Return the display width of the widest line of `s`, not counting trailing whitespace.
+pub fn trimmed_block_width(s: &str) -> usize {
+    s.split('\n')
+        .map(|line| display_width(line.trim_end()))
+        .max()
+        .unwrap_or(0)
+}
+
+/// Return the number of terminal columns a single character occupies.
+pub fn char_width(c: char) -> usize {
+    match c as u32 {
+        0x0000..=0x001F | 0x007F..=0x009F => 0,
+        0x0300..=0x036F | 0x200B..=0x200F | 0xFE00..=0xFE0F => 0,
+        0x1100..=0x115F
+        | 0x2E80..=0x303E
+        | 0x3041..=0x33FF
+        | 0x3400..=0x4DBF
+        | 0x4E00..=0x9FFF
+        | 0xA000..=0xA4CF
+        | 0xAC00..=0xD7A3
+        | 0xF900..=0xFAFF
+        | 0xFE30..=0xFE4F
+        | 0xFF00..=0xFF60
+        | 0xFFE0..=0xFFE6
+        | 0x1F300..=0x1F64F
+        | 0x1F900..=0x1F9FF
+        | 0x20000..=0x3FFFD => 2,
+        _ => 1,
+    }
+}
+
+/// Truncate `s` to at most `width` display columns, marking the cut with `…`.
+pub fn truncate_to_width(s: &str, width: usize) -> String {
+    if display_width(s) <= width {
+        return s.to_string();
+    }
+    if width == 0 {
+        return String::new();
+    }
+    let mut truncated = String::new();
+    let mut used = 0;
+    for c in s.chars() {
+        let w = char_width(c);
+        if used + w > width - 1 {
+            break;
+        }
+        truncated.push(c);
+        used += w;
+    }
+    truncated.push('…');
+    truncated
+}
+
+/// Truncate `s` to at most `width` display columns by cutting out its middle, marking the cut with `…`.
+///
+/// `ratio` is the share of the remaining width kept from the start of `s`; the
+/// rest is kept from its end.
+///
+pub fn truncate_middle(s: &str, width: usize, ratio: f64) -> String {
+    if display_width(s) <= width {
+        return s.to_string();
+    }
+    if width == 0 {
+        return String::new();
+    }
+    let available = width - 1;
+    let head_width = (available as f64 * ratio.clamp(0.0, 1.0)).round() as usize;
+    let mut head = String::new();
+    let mut used = 0;
+    for c in s.chars() {
+        if used + char_width(c) > head_width {
+            break;
+        }
+        head.push(c);
+        used += char_width(c);
+    }
+    let mut tail = Vec::new();
+    let mut tail_used = 0;
+    for c in s.chars().rev() {
+        if used + tail_used + char_width(c) > available {
+            break;
+        }
+        tail.push(c);
+        tail_used += char_width(c);
+    }
+    head.push('…');
+    head.extend(tail.into_iter().rev());
+    head
+}
+
+/// Wrap each line of `s` at word boundaries so that no line is wider than `width`.
+///
+/// Words wider than `width` are split across lines.
+///
+pub fn wrap_to_width(s: &str, width: usize) -> String {
+    let width = width.max(1);
+    let mut lines: Vec<String> = Vec::new();
+    for line in s.split('\n') {
+        let mut current = String::new();
+        let mut used = 0;
+        for word in line.split_whitespace() {
+            let mut word_width = display_width(word);
+            if used > 0 && used + 1 + word_width <= width {
+                current.push(' ');
+                current.push_str(word);
+                used += 1 + word_width;
+                continue;
+            }
+            if used > 0 {
+                lines.push(std::mem::take(&mut current));
+            }
+            let mut rest = word;
+            while word_width > width {
+                let mut split = 0;
+                let mut split_width = 0;
+                for (i, c) in rest.char_indices() {
+                    if split_width + char_width(c) > width {
+                        break;
+                    }
+                    split_width += char_width(c);
+                    split = i + c.len_utf8();
+                }
+                if split == 0 {
+                    split = rest.chars().next().map_or(rest.len(), char::len_utf8);
+                }
+                lines.push(rest[..split].to_string());
+                rest = &rest[split..];
+                word_width = display_width(rest);
+            }
+            current.push_str(rest);
+            used = word_width;
+        }
+        lines.push(current);
+    }
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_display_width() {
+        assert_eq!(display_width("name"), 4);
+        assert_eq!(display_width("日本"), 4);
+        assert_eq!(display_width("\x1b[32mok\x1b[0m"), 2);
+        assert_eq!(truncate_to_width("日本語", 5), "日本…");
+        assert_eq!(truncate_to_width("abc", 3), "abc");
+    }
+
+    #[test]
+    fn test_wrap_to_width() {
+        assert_eq!(
+            wrap_to_width("total number of requests", 10),
+            "total\nnumber of\nrequests"
+        );
+        assert_eq!(wrap_to_width("abcdefghij", 4), "abcd\nefgh\nij");
+        assert_eq!(wrap_to_width("first\nname", 10), "first\nname");
+        assert_eq!(wrap_to_width("id", 10), "id");
+    }
+
+    #[test]
+    fn test_hyperlink() {
+        let link = hyperlink("https://example.com/a", "example");
+        assert_eq!(
+            link,
+            "\x1b]8;;https://example.com/a\x1b\\example\x1b]8;;\x1b\\"
+        );
+        assert_eq!(display_width(&link), display_width("example"));
+        assert_eq!(display_width("\x1b]8;;http://a\x07a\x1b]8;;\x07"), 1);
+        assert!(looks_like_url("https://example.com"));
+        assert!(!looks_like_url("example.com"));
+    }
+
+    #[test]
+    fn test_truncate_middle() {
+        assert_eq!(
+            truncate_middle("/very/long/path/to/file", 13, 0.5),
+            "/very/…o/file"
+        );
+        assert_eq!(
+            truncate_middle("/very/long/path/to/file", 13, 0.25),
+            "/ve…h/to/file"
+        );
+        assert_eq!(truncate_middle("日本語テキスト", 7, 0.5), "日…スト");
+        assert_eq!(truncate_middle("short", 5, 0.5), "short");
+        assert_eq!(truncate_middle("abc", 0, 0.5), "");
+    }
+
+    #[test]
+    fn test_sanitize() {
+        assert_eq!(sanitize("\x1b[31mred\x1b[0m"), "red");
+        assert_eq!(sanitize("\x1b]0;pwned\x07title"), "title");
+        assert_eq!(sanitize("\x1b]8;;http://x\x1b\\link\x1b]8;;\x1b\\"), "link");
+        assert_eq!(sanitize("a\rb\x08c\td\ne"), "abc d\ne");
+        assert_eq!(sanitize("\x1bcreset"), "reset");
+        assert_eq!(sanitize("日本"), "日本");
+    }
+}
@@ -0,0 +1,993 @@
+//! Reshaping of parsed rows before they are rendered.
+
+use crate::error::TabbsError;
+use crate::expr::Expr;
+use crate::number::abbreviate_number;
+use crate::text::sanitize;
+use crate::types::{detect_column_type, detect_type, parse_bool, ColumnType};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+/// Pad short rows with empty cells and truncate long ones to `field_count` fields.
+///
+/// Returns the number of rows that were adjusted.
+///
+pub fn repair_rows(rows: &mut [Vec<String>], field_count: usize) -> usize {
+    let mut repaired = 0;
+    for row in rows.iter_mut() {
+        if row.len() != field_count {
+            row.resize(field_count, String::new());
+            repaired += 1;
+        }
+    }
+    repaired
+}
+
+/// Return an error naming the line of the first record whose field count
+/// differs from `field_count`.
+pub fn check_field_counts(
+    records: &[(usize, Vec<String>)],
+    field_count: usize,
+) -> Result<(), TabbsError> {
+    match records
+        .iter()
+        .find(|(_, record)| record.len() != field_count)
+    {
+        Some((line, record)) => Err(TabbsError::Parse {
+            line: *line,
+            message: format!("expected {} fields, found {}", field_count, record.len()),
+        }),
+        None => Ok(()),
+    }
+}
+
+/// Keep the records whose field count differs from `field_count`, as rows of the
+/// line number, the field count and the fields.
+///
+/// Short records are padded with empty fields and long ones cut to `field_count`.
+///
+pub fn ragged_rows(records: Vec<(usize, Vec<String>)>, field_count: usize) -> Vec<Vec<String>> {
+    records
+        .into_iter()
+        .filter(|(_, record)| record.len() != field_count)
+        .map(|(line, mut record)| {
+            let fields = record.len();
+            record.resize(field_count, String::new());
+            [line.to_string(), fields.to_string()]
+                .into_iter()
+                .chain(record)
+                .collect()
+        })
+        .collect()
+}
+
+/// Remove ANSI escape sequences and control characters other than line breaks from every cell.
+pub fn sanitize_rows(rows: &mut [Vec<String>]) {
+    for cell in rows.iter_mut().flatten() {
+        if cell.contains(|c: char| c.is_control() && c != '\n') {
+            *cell = sanitize(cell);
+        }
+    }
+}
+
+/// Remove the rows whose cells are all empty or whitespace, returning how many were removed.
+pub fn drop_empty_rows(rows: &mut Vec<Vec<String>>) -> usize {
+    let count = rows.len();
+    rows.retain(|row| row.iter().any(|cell| !cell.trim().is_empty()));
+    count - rows.len()
+}
+
+/// Remove the columns that are empty or whitespace in every row, returning how many were removed.
+pub fn drop_empty_columns(column_names: &mut Vec<String>, rows: &mut [Vec<String>]) -> usize {
+    let order: Vec<usize> = (0..column_names.len())
+        .filter(|&i| {
+            rows.iter()
+                .any(|row| row.get(i).is_some_and(|cell| !cell.trim().is_empty()))
+        })
+        .collect();
+    let dropped = column_names.len() - order.len();
+    if dropped > 0 {
+        reorder_columns(column_names, rows, &order);
+    }
+    dropped
+}
+
+/// Keep at most `limit` rows, returning how many were dropped.
+pub fn limit_rows(rows: &mut Vec<Vec<String>>, limit: usize) -> usize {
+    let dropped = rows.len().saturating_sub(limit);
+    rows.truncate(limit);
+    dropped
+}
+
+/// Rearrange the columns so that column `i` becomes the former column `order[i]`.
+///
+/// Cells missing from short rows are filled with empty strings.
+///
+pub fn reorder_columns(column_names: &mut Vec<String>, rows: &mut [Vec<String>], order: &[usize]) {
+    *column_names = order.iter().map(|&i| column_names[i].clone()).collect();
+    for row in rows.iter_mut() {
+        *row = order
+            .iter()
+            .map(|&i| row.get(i).cloned().unwrap_or_default())
+            .collect();
+    }
+}
+
+/// Keep only the columns named in `names`, in that order.
+pub fn select_columns(
+    column_names: &mut Vec<String>,
+    rows: &mut [Vec<String>],
+    names: &[&str],
+) -> Result<(), TabbsError> {
+    let order = names
+        .iter()
+        .map(|name| {
+            column_names
+                .iter()
+                .position(|column| column == name)
+                .ok_or_else(|| TabbsError::Config(format!("Unknown column: {}", name)))
+        })
+        .collect::<Result<Vec<usize>, TabbsError>>()?;
+    reorder_columns(column_names, rows, &order);
+    Ok(())
+}
+
+/// Sort the columns alphabetically by header name, keeping equal names in order.
+pub fn sort_columns(column_names: &mut Vec<String>, rows: &mut [Vec<String>]) {
+    let mut order: Vec<usize> = (0..column_names.len()).collect();
+    order.sort_by(|&a, &b| column_names[a].cmp(&column_names[b]));
+    reorder_columns(column_names, rows, &order);
+}
+
+/// Sort the rows by the column named `name`, keeping equal rows in order.
+///
+/// Numeric cells come before all others and are compared by value, while the
+/// rest are compared as text. Returns the original position of each row in the
+/// sorted order.
+///
+pub fn sort_rows(
+    column_names: &[String],
+    rows: &mut Vec<Vec<String>>,
+    name: &str,
+) -> Result<Vec<usize>, TabbsError> {
+    let index = column_names
+        .iter()
+        .position(|column| column == name)
+        .ok_or_else(|| TabbsError::Config(format!("Unknown column: {}", name)))?;
+    let cell = |i: usize| rows[i].get(index).map_or("", |cell| cell.trim());
+    let mut order: Vec<usize> = (0..rows.len()).collect();
+    order.sort_by(|&a, &b| {
+        let (a, b) = (cell(a), cell(b));
+        match (a.parse::<f64>(), b.parse::<f64>()) {
+            (Ok(x), Ok(y)) => x.total_cmp(&y),
+            (Ok(_), Err(_)) => Ordering::Less,
+            (Err(_), Ok(_)) => Ordering::Greater,
+            (Err(_), Err(_)) => a.cmp(b),
+        }
+    });
+    let mut taken: Vec<Option<Vec<String>>> = rows.drain(..).map(Some).collect();
+    rows.extend(order.iter().map(|&i| taken[i].take().unwrap()));
+    Ok(order)
+}
+
+/// Bring the rows sharing a cell in the column named `name` together.
+///
+/// Groups appear in the order their values first appear, and rows keep their
+/// order within each group. Returns the original position of each row in the
+/// new order.
+///
+pub fn group_by(
+    column_names: &[String],
+    rows: &mut Vec<Vec<String>>,
+    name: &str,
+) -> Result<Vec<usize>, TabbsError> {
+    let index = column_names
+        .iter()
+        .position(|column| column == name)
+        .ok_or_else(|| TabbsError::Config(format!("Unknown column: {}", name)))?;
+    let mut first_seen: HashMap<&str, usize> = HashMap::new();
+    let groups: Vec<usize> = rows
+        .iter()
+        .map(|row| {
+            let value = row.get(index).map_or("", String::as_str);
+            let next = first_seen.len();
+            *first_seen.entry(value).or_insert(next)
+        })
+        .collect();
+    let mut order: Vec<usize> = (0..rows.len()).collect();
+    order.sort_by_key(|&i| groups[i]);
+    let mut taken: Vec<Option<Vec<String>>> = rows.drain(..).map(Some).collect();
+    rows.extend(order.iter().map(|&i| taken[i].take().unwrap()));
+    Ok(order)
+}
+
+/// Insert a `#` column at the front holding `numbers`, one per row.
+pub fn insert_row_numbers(
+    column_names: &mut Vec<String>,
+    rows: &mut [Vec<String>],
+    numbers: &[usize],
+) {
+    column_names.insert(0, "#".to_string());
+    for (row, number) in rows.iter_mut().zip(numbers) {
+        row.insert(0, number.to_string());
+    }
+}
+
+/// Replace every non-empty cell of the columns named in `names` with `mask`.
+pub fn mask_columns(
+    column_names: &[String],
+    rows: &mut [Vec<String>],
+    names: &[&str],
+    mask: &str,
+) -> Result<(), TabbsError> {
+    for name in names {
+        let index = column_names
+            .iter()
+            .position(|column| column == name)
+            .ok_or_else(|| TabbsError::Config(format!("Unknown column: {}", name)))?;
+        for cell in rows.iter_mut().filter_map(|row| row.get_mut(index)) {
+            if !cell.is_empty() {
+                *cell = mask.to_string();
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Replace the cells of the columns named in `names` that repeat the cell above
+/// them with `mark`, so only the first value of each run is shown.
+pub fn ditto_columns(
+    column_names: &[String],
+    rows: &mut [Vec<String>],
+    names: &[&str],
+    mark: &str,
+) -> Result<(), TabbsError> {
+    for name in names {
+        let index = column_names
+            .iter()
+            .position(|column| column == name)
+            .ok_or_else(|| TabbsError::Config(format!("Unknown column: {}", name)))?;
+        let mut previous: Option<String> = None;
+        for cell in rows.iter_mut().filter_map(|row| row.get_mut(index)) {
+            if previous.as_ref() == Some(cell) {
+                *cell = mark.to_string();
+            } else {
+                previous = Some(cell.clone());
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Round every decimal cell to `places` decimal places.
+///
+/// Integers, non-numeric cells and columns declared `Text` in `column_types` are
+/// left untouched.
+///
+pub fn round_numbers(rows: &mut [Vec<String>], column_types: &[Option<ColumnType>], places: usize) {
+    for row in rows.iter_mut() {
+        for (i, cell) in row.iter_mut().enumerate() {
+            if column_types.get(i) == Some(&Some(ColumnType::Text)) {
+                continue;
+            }
+            if detect_type(cell) == ColumnType::Float {
+                let value: f64 = cell.trim().parse().unwrap();
+                *cell = format!("{:.*}", places, value);
+            }
+        }
+    }
+}
+
+/// Abbreviate every numeric cell of at least one thousand, such as `1500` to `1.5K`.
+///
+/// `precision` sets the number of decimal places as described for `abbreviate_number`.
+/// Columns declared `Text` in `column_types` are left untouched.
+///
+pub fn abbreviate_numbers(
+    rows: &mut [Vec<String>],
+    column_types: &[Option<ColumnType>],
+    precision: Option<usize>,
+) {
+    for row in rows.iter_mut() {
+        for (i, cell) in row.iter_mut().enumerate() {
+            if column_types.get(i) == Some(&Some(ColumnType::Text)) {
+                continue;
+            }
+            if detect_type(cell).is_numeric() {
+                let value: f64 = cell.trim().parse().unwrap();
+                if let Some(abbreviated) = abbreviate_number(value, precision) {
+                    *cell = abbreviated;
+                }
+            }
+        }
+    }
+}
+
+/// Append a column named `name` holding the value of `expr` for each row.
+///
+/// Rows where a referenced cell is not a number get an empty cell. Returns an
+/// error if `expr` refers to an unknown column.
+///
+pub fn compute_column(
+    column_names: &mut Vec<String>,
+    rows: &mut [Vec<String>],
+    name: &str,
+    expr: &Expr,
+) -> Result<(), TabbsError> {
+    let mut indices = Vec::new();
+    for column in expr.columns() {
+        let index = column_names
+            .iter()
+            .position(|name| name == column)
+            .ok_or_else(|| TabbsError::Config(format!("Unknown column: {}", column)))?;
+        indices.push((column, index));
+    }
+    for row in rows.iter_mut() {
+        let value = expr.eval(&|column: &str| {
+            let (_, index) = indices.iter().find(|(name, _)| *name == column)?;
+            row.get(*index)?.trim().parse().ok()
+        });
+        row.resize(column_names.len(), String::new());
+        row.push(value.map(|value| value.to_string()).unwrap_or_default());
+    }
+    column_names.push(name.to_string());
+    Ok(())
+}
+
+/// Append a column holding the running sum of the numeric column `name` down the rows.
+///
+/// The new column is named `cum_` followed by `name`. A cell that is not a number
+/// keeps the total unchanged, or with `reset`, leaves its row empty and starts the
+/// sum again from zero. Totals have as many decimal places as the most precise
+/// number summed into them, so `0.1` and `0.2` add up to `0.3`. Returns an error if there
+/// is no column `name`.
+///
+pub fn cumulative_column(
+    column_names: &mut Vec<String>,
+    rows: &mut [Vec<String>],
+    name: &str,
+    reset: bool,
+) -> Result<(), TabbsError> {
+    let index = column_names
+        .iter()
+        .position(|column| column == name)
+        .ok_or_else(|| TabbsError::Config(format!("Unknown column: {}", name)))?;
+    let mut total = 0.0;
+    let mut places = 0;
+    for row in rows.iter_mut() {
+        let value = row.get(index).and_then(|cell| {
+            let cell = cell.trim();
+            cell.parse::<f64>()
+                .ok()
+                .map(|value| (value, decimal_places(cell)))
+        });
+        let cell = match value {
+            Some((value, decimals)) => {
+                total += value;
+                places = places.max(decimals);
+                format!("{:.*}", places, total)
+            }
+            None if reset => {
+                total = 0.0;
+                places = 0;
+                String::new()
+            }
+            None => format!("{:.*}", places, total),
+        };
+        row.resize(column_names.len(), String::new());
+        row.push(cell);
+    }
+    column_names.push(format!("cum_{}", name));
+    Ok(())
+}
+
+/// Return the number of decimal places written in the number `cell`, such as 2 for
+/// `1.25` and 3 for `2.5e-2`.
+fn decimal_places(cell: &str) -> usize {
+    let (mantissa, exponent) = cell.split_once(['e', 'E']).unwrap_or((cell, "0"));
+    let fraction = mantissa
+        .split_once('.')
+        .map_or(0, |(_, fraction)| fraction.len());
+    let exponent: i64 = exponent.parse().unwrap_or(0);
+    (fraction as i64 - exponent).max(0) as usize
+}
+
+/// Stack the values separated by `separator` in each cell of column `name` on separate lines.
+///
+/// Each value is trimmed, so the rendered cell lists them one per line. Returns an
+/// error if there is no column `name`.
+///
+pub fn expand_column(
+    column_names: &[String],
+    rows: &mut [Vec<String>],
+    name: &str,
+    separator: char,
+) -> Result<(), TabbsError> {
+    let index = column_names
+        .iter()
+        .position(|column| column == name)
+        .ok_or_else(|| TabbsError::Config(format!("Unknown column: {}", name)))?;
+    for cell in rows.iter_mut().filter_map(|row| row.get_mut(index)) {
+        if cell.contains(separator) {
+            *cell = cell
+                .split(separator)
+                .map(str::trim)
+                .collect::<Vec<_>>()
+                .join("\n");
+        }
+    }
+    Ok(())
+}
+
+/// Join the values separated by `separator` in each cell of column `name` with `, `.
+///
+/// Each value is trimmed and empty values are dropped, so `a| b||c` shows as
+/// `a, b, c`. Returns an error if there is no column `name`.
+///
+pub fn flatten_column(
+    column_names: &[String],
+    rows: &mut [Vec<String>],
+    name: &str,
+    separator: char,
+) -> Result<(), TabbsError> {
+    let index = column_names
+        .iter()
+        .position(|column| column == name)
+        .ok_or_else(|| TabbsError::Config(format!("Unknown column: {}", name)))?;
+    for cell in rows.iter_mut().filter_map(|row| row.get_mut(index)) {
+        if cell.contains(separator) {
+            *cell = cell
+                .split(separator)
+                .map(str::trim)
+                .filter(|value| !value.is_empty())
+                .collect::<Vec<_>>()
+                .join(", ");
+        }
+    }
+    Ok(())
+}
+
+/// Keep a uniform random sample of `count` rows, in their original order.
+///
+/// The sample is drawn by reservoir sampling in a single pass, from a generator
+/// seeded with `seed`, so the same seed always keeps the same rows. Returns the
+/// original position of each kept row.
+///
+pub fn sample_rows(rows: &mut Vec<Vec<String>>, count: usize, seed: u64) -> Vec<usize> {
+    // splitmix64, which turns any seed, zero included, into a well-mixed sequence.
+    let mut state = seed;
+    let mut next = move || {
+        state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    };
+    let mut reservoir: Vec<usize> = Vec::with_capacity(count.min(rows.len()));
+    for i in 0..rows.len() {
+        if reservoir.len() < count {
+            reservoir.push(i);
+        } else {
+            let j = (next() % (i as u64 + 1)) as usize;
+            if j < count {
+                reservoir[j] = i;
+            }
+        }
+    }
+    reservoir.sort_unstable();
+    let mut taken: Vec<Option<Vec<String>>> = rows.drain(..).map(Some).collect();
+    rows.extend(reservoir.iter().filter_map(|&i| taken[i].take()));
+    reservoir
+}
+
+/// Rotate the grid of the header and rows 90 degrees, so that column `i` becomes row `i`.
+///
+/// The first column, header cell included, becomes the new header. Cells missing
+/// from short rows are filled with empty strings.
+///
+pub fn rotate(column_names: &mut Vec<String>, rows: &mut Vec<Vec<String>>) {
+    let width = rows
+        .iter()
+        .map(Vec::len)
+        .fold(column_names.len(), usize::max);
+    let mut rotated: Vec<Vec<String>> = (0..width)
+        .map(|i| {
+            std::iter::once(&*column_names)
+                .chain(rows.iter())
+                .map(|row| row.get(i).cloned().unwrap_or_default())
+                .collect()
+        })
+        .collect();
+    *column_names = if rotated.is_empty() {
+        Vec::new()
+    } else {
+        rotated.remove(0)
+    };
+    *rows = rotated;
+}
+
+/// Replace the values of boolean columns with `yes` and `no` symbols, such as `✓` and `✗`.
+///
+/// A column is boolean when every non-empty value is `true`/`false` or `yes`/`no`,
+/// or every value is `1`/`0` and both occur. Columns with a declared type in
+/// `column_types` are only converted when it is `Boolean`, in which case `1` and
+/// `0` are accepted too.
+///
+pub fn replace_booleans(
+    rows: &mut [Vec<String>],
+    column_types: &[Option<ColumnType>],
+    yes: &str,
+    no: &str,
+) {
+    let read = |value: &str| match value.trim() {
+        "1" => Some(true),
+        "0" => Some(false),
+        value => parse_bool(value),
+    };
+    for (i, declared) in column_types.iter().enumerate() {
+        let values = || rows.iter().filter_map(|row| row.get(i).map(String::as_str));
+        let boolean = match declared {
+            Some(kind) => *kind == ColumnType::Boolean,
+            None => match detect_column_type(values()) {
+                ColumnType::Boolean => true,
+                ColumnType::Integer => {
+                    values().all(|value| value.trim().is_empty() || read(value).is_some())
+                        && values().any(|value| read(value) == Some(true))
+                        && values().any(|value| read(value) == Some(false))
+                }
+                _ => false,
+            },
+        };
+        if !boolean {
+            continue;
+        }
+        for cell in rows.iter_mut().filter_map(|row| row.get_mut(i)) {
+            match read(cell) {
+                Some(true) => *cell = yes.to_string(),
+                Some(false) => *cell = no.to_string(),
+                None => {}
+            }
+        }
+    }
+}
+
+/// Replace the table with the distinct values of column `name` and how often each occurs.
+///
+/// The new columns are `name` and `count`, sorted by count from most to least
+/// frequent, with ties in the order their values first appear. Returns an error
+/// if there is no column `name`.
+///
+pub fn histogram(
+    column_names: &mut Vec<String>,
+    rows: &mut Vec<Vec<String>>,
+    name: &str,
+) -> Result<(), TabbsError> {
+    let index = column_names
+        .iter()
+        .position(|column| column == name)
+        .ok_or_else(|| TabbsError::Config(format!("Unknown column: {}", name)))?;
+    let mut positions: HashMap<&str, usize> = HashMap::new();
+    let mut counts: Vec<(&str, usize)> = Vec::new();
+    for row in rows.iter() {
+        let value = row.get(index).map_or("", String::as_str);
+        match positions.get(value) {
+            Some(&position) => counts[position].1 += 1,
+            None => {
+                positions.insert(value, counts.len());
+                counts.push((value, 1));
+            }
+        }
+    }
+    counts.sort_by_key(|&(_, count)| std::cmp::Reverse(count));
+    let histogram: Vec<Vec<String>> = counts
+        .into_iter()
+        .map(|(value, count)| vec![value.to_string(), count.to_string()])
+        .collect();
+    *rows = histogram;
+    *column_names = vec![name.to_string(), "count".to_string()];
+    Ok(())
+}
+
+/// Count the rows sharing each cell of column `index`, in the order the values
+/// first appear.
+pub fn group_counts(rows: &[Vec<String>], index: usize) -> Vec<(String, usize)> {
+    let mut counts: Vec<(String, usize)> = Vec::new();
+    for row in rows {
+        let value = row.get(index).map_or("", String::as_str);
+        match counts.iter_mut().find(|(group, _)| group == value) {
+            Some((_, count)) => *count += 1,
+            None => counts.push((value.to_string(), 1)),
+        }
+    }
+    counts
+}
+
+/// Split `rows` into groups sharing the same cell in column `index`.
+///
+/// Groups are returned in the order their values first appear, and rows keep
+/// their order within each group. Rows missing the column form the `""` group.
+///
+pub fn group_rows(rows: &[Vec<String>], index: usize) -> Vec<(String, Vec<Vec<String>>)> {
+    let mut groups: Vec<(String, Vec<Vec<String>>)> = Vec::new();
+    for row in rows {
+        let value = row.get(index).map_or("", String::as_str);
+        match groups.iter_mut().find(|(group, _)| group == value) {
+            Some((_, group_rows)) => group_rows.push(row.clone()),
+            None => groups.push((value.to_string(), vec![row.clone()])),
+        }
+    }
+    groups
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(values: &[&str]) -> Vec<String> {
+        values.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_repair_rows() {
+        let mut rows = vec![
+            strings(&["jack", "35", "neat"]),
+            strings(&["jane"]),
+            strings(&["erin", "20", "ah", "x"]),
+            strings(&["finn", "41", "ok"]),
+        ];
+        assert_eq!(repair_rows(&mut rows, 3), 2);
+        assert!(rows.iter().all(|row| row.len() == 3));
+        assert_eq!(rows[1], vec!["jane", "", ""]);
+        assert_eq!(rows[2], vec!["erin", "20", "ah"]);
+        assert_eq!(repair_rows(&mut rows, 3), 0);
+    }
+
+    #[test]
+    fn test_sort_columns() {
+        let mut column_names = strings(&["name", "age", "text"]);
+        let mut rows = vec![strings(&["jack", "35", "neat"]), strings(&["jane", "50"])];
+        sort_columns(&mut column_names, &mut rows);
+        assert_eq!(column_names, vec!["age", "name", "text"]);
+        assert_eq!(rows[0], vec!["35", "jack", "neat"]);
+        assert_eq!(rows[1], vec!["50", "jane", ""]);
+
+        let mut column_names = strings(&["name", "age", "text"]);
+        let mut rows = vec![strings(&["jack", "35", "neat"])];
+        select_columns(&mut column_names, &mut rows, &["text", "name"]).unwrap();
+        sort_columns(&mut column_names, &mut rows);
+        assert_eq!(column_names, vec!["name", "text"]);
+        assert_eq!(rows[0], vec!["jack", "neat"]);
+        assert!(select_columns(&mut column_names, &mut rows, &["missing"]).is_err());
+    }
+
+    #[test]
+    fn test_round_numbers() {
+        let mut rows = vec![
+            strings(&["pi", "3.14159", "35"]),
+            strings(&["e", "2.7", "n/a"]),
+        ];
+        round_numbers(&mut rows, &[], 2);
+        assert_eq!(rows[0], vec!["pi", "3.14", "35"]);
+        assert_eq!(rows[1], vec!["e", "2.70", "n/a"]);
+
+        let mut rows = vec![strings(&["1.10", "1.10"])];
+        round_numbers(&mut rows, &[Some(ColumnType::Text), None], 0);
+        assert_eq!(rows[0], vec!["1.10", "1"]);
+    }
+
+    #[test]
+    fn test_compute_column() {
+        let mut column_names = strings(&["item", "price", "qty"]);
+        let mut rows = vec![
+            strings(&["tea", "2.5", "4"]),
+            strings(&["cake", "3", "n/a"]),
+        ];
+        let (name, expr) = crate::expr::parse_compute("total = price * qty").unwrap();
+        compute_column(&mut column_names, &mut rows, &name, &expr).unwrap();
+        assert_eq!(column_names, vec!["item", "price", "qty", "total"]);
+        assert_eq!(rows[0], vec!["tea", "2.5", "4", "10"]);
+        assert_eq!(rows[1], vec!["cake", "3", "n/a", ""]);
+
+        let expr = crate::expr::parse_expr("price * stock").unwrap();
+        assert!(compute_column(&mut column_names, &mut rows, "value", &expr).is_err());
+    }
+
+    #[test]
+    fn test_group_rows() {
+        let rows = vec![
+            strings(&["tea", "drink"]),
+            strings(&["cake", "food"]),
+            strings(&["coffee", "drink"]),
+        ];
+        let groups = group_rows(&rows, 1);
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[0].0, "drink");
+        assert_eq!(groups[0].1, vec![rows[0].clone(), rows[2].clone()]);
+        assert_eq!(groups[1], ("food".to_string(), vec![rows[1].clone()]));
+    }
+
+    #[test]
+    fn test_abbreviate_numbers() {
+        let mut rows = vec![strings(&["views", "1500", "2000000", "999", "n/a"])];
+        abbreviate_numbers(&mut rows, &[], None);
+        assert_eq!(rows[0], vec!["views", "1.5K", "2M", "999", "n/a"]);
+
+        let mut rows = vec![strings(&["123456", "123456"])];
+        abbreviate_numbers(&mut rows, &[Some(ColumnType::Text), None], None);
+        assert_eq!(rows[0], vec!["123456", "123.5K"]);
+    }
+
+    #[test]
+    fn test_mask_columns() {
+        let column_names = strings(&["name", "email"]);
+        let mut rows = vec![
+            strings(&["jack", "jack@example.com"]),
+            strings(&["jane", ""]),
+        ];
+        mask_columns(&column_names, &mut rows, &["email"], "****").unwrap();
+        assert_eq!(rows[0], vec!["jack", "****"]);
+        assert_eq!(rows[1], vec!["jane", ""]);
+        assert!(mask_columns(&column_names, &mut rows, &["phone"], "****").is_err());
+    }
+
+    #[test]
+    fn test_sort_rows_keeps_original_index() {
+        let mut column_names = strings(&["name", "age"]);
+        let mut rows = vec![
+            strings(&["jack", "35"]),
+            strings(&["jane", "5"]),
+            strings(&["erin", "20"]),
+        ];
+        let order = sort_rows(&column_names, &mut rows, "age").unwrap();
+        assert_eq!(order, vec![1, 2, 0]);
+        let numbers: Vec<usize> = order.iter().map(|i| i + 1).collect();
+        insert_row_numbers(&mut column_names, &mut rows, &numbers);
+        assert_eq!(column_names, vec!["#", "name", "age"]);
+        assert_eq!(
+            rows,
+            vec![
+                strings(&["2", "jane", "5"]),
+                strings(&["3", "erin", "20"]),
+                strings(&["1", "jack", "35"]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_sort_rows_mixed() {
+        let column_names = strings(&["v"]);
+        let mut rows: Vec<Vec<String>> = ["b", "10", "", "9", "a", "-1"]
+            .iter()
+            .map(|cell| strings(&[cell]))
+            .collect();
+        sort_rows(&column_names, &mut rows, "v").unwrap();
+        let sorted: Vec<&str> = rows.iter().map(|row| row[0].as_str()).collect();
+        assert_eq!(sorted, vec!["-1", "9", "10", "", "a", "b"]);
+    }
+
+    #[test]
+    fn test_drop_empty_rows() {
+        let mut rows = vec![
+            strings(&["jack", "35"]),
+            strings(&["", " "]),
+            strings(&[]),
+            strings(&["", "50"]),
+        ];
+        assert_eq!(drop_empty_rows(&mut rows), 2);
+        assert_eq!(rows, vec![strings(&["jack", "35"]), strings(&["", "50"])]);
+    }
+
+    #[test]
+    fn test_drop_empty_columns() {
+        let mut column_names = strings(&["name", "note", "age"]);
+        let mut rows = vec![
+            strings(&["jack", "", "35"]),
+            strings(&["jane", "", "50"]),
+            strings(&["erin", "late"]),
+        ];
+        assert_eq!(drop_empty_columns(&mut column_names, &mut rows), 0);
+        limit_rows(&mut rows, 2);
+        assert_eq!(drop_empty_columns(&mut column_names, &mut rows), 1);
+        assert_eq!(column_names, vec!["name", "age"]);
+        assert_eq!(
+            rows,
+            vec![strings(&["jack", "35"]), strings(&["jane", "50"])]
+        );
+    }
+
+    #[test]
+    fn test_rotate() {
+        let mut column_names = strings(&["name", "age"]);
+        let mut rows = vec![
+            strings(&["jack", "35"]),
+            strings(&["jane", "50"]),
+            strings(&["erin", "20"]),
+        ];
+        rotate(&mut column_names, &mut rows);
+        assert_eq!(column_names, vec!["name", "jack", "jane", "erin"]);
+        assert_eq!(rows, vec![strings(&["age", "35", "50", "20"])]);
+        rotate(&mut column_names, &mut rows);
+        assert_eq!(column_names, vec!["name", "age"]);
+        assert_eq!(rows.len(), 3);
+    }
+
+    #[test]
+    fn test_replace_booleans() {
+        let mut rows = vec![
+            strings(&["jack", "yes", "1", "35", "1"]),
+            strings(&["jane", "No", "0", "0", "0"]),
+        ];
+        let column_types = [None, None, None, None, Some(ColumnType::Text)];
+        replace_booleans(&mut rows, &column_types, "✓", "✗");
+        assert_eq!(rows[0], vec!["jack", "✓", "✓", "35", "1"]);
+        assert_eq!(rows[1], vec!["jane", "✗", "✗", "0", "0"]);
+
+        let mut rows = vec![strings(&["1", "1", "0"]), strings(&["1", "0", "1"])];
+        let column_types = [None, Some(ColumnType::Integer), None];
+        replace_booleans(&mut rows, &column_types, "✓", "✗");
+        assert_eq!(rows[0], vec!["1", "1", "✗"]);
+        assert_eq!(rows[1], vec!["1", "0", "✓"]);
+    }
+
+    #[test]
+    fn test_ragged_rows() {
+        let records = vec![
+            (1, strings(&["jack", "35", "neat"])),
+            (2, strings(&["jane", "50"])),
+            (4, strings(&["erin", "20", "ah"])),
+        ];
+        assert_eq!(
+            ragged_rows(records, 3),
+            vec![strings(&["2", "2", "jane", "50", ""])]
+        );
+    }
+
+    #[test]
+    fn test_cumulative_column() {
+        let rows = vec![
+            strings(&["mon", "3"]),
+            strings(&["tue", "2.5"]),
+            strings(&["wed", "n/a"]),
+            strings(&["thu", "4"]),
+        ];
+        let mut names = strings(&["day", "sales"]);
+        let mut skipped = rows.clone();
+        cumulative_column(&mut names, &mut skipped, "sales", false).unwrap();
+        assert_eq!(names, vec!["day", "sales", "cum_sales"]);
+        let totals: Vec<&str> = skipped.iter().map(|row| row[2].as_str()).collect();
+        assert_eq!(totals, vec!["3", "5.5", "5.5", "9.5"]);
+
+        let mut names = strings(&["day", "sales"]);
+        let mut reset = rows;
+        cumulative_column(&mut names, &mut reset, "sales", true).unwrap();
+        let totals: Vec<&str> = reset.iter().map(|row| row[2].as_str()).collect();
+        assert_eq!(totals, vec!["3", "5.5", "", "4"]);
+
+        let mut names = strings(&["sales"]);
+        let mut rows = vec![strings(&["0.1"]), strings(&["0.2"]), strings(&["0.3"])];
+        cumulative_column(&mut names, &mut rows, "sales", false).unwrap();
+        let totals: Vec<&str> = rows.iter().map(|row| row[1].as_str()).collect();
+        assert_eq!(totals, vec!["0.1", "0.3", "0.6"]);
+
+        assert!(cumulative_column(&mut names, &mut reset, "bogus", false).is_err());
+    }
+
+    #[test]
+    fn test_expand_column() {
+        let names = strings(&["name", "tags"]);
+        let mut rows = vec![strings(&["jack", "a| b |c"]), strings(&["jane", "solo"])];
+        expand_column(&names, &mut rows, "tags", '|').unwrap();
+        assert_eq!(rows[0][1], "a\nb\nc");
+        assert_eq!(rows[1][1], "solo");
+        assert!(expand_column(&names, &mut rows, "bogus", '|').is_err());
+    }
+
+    #[test]
+    fn test_flatten_column() {
+        let names = strings(&["name", "tags"]);
+        let mut rows = vec![strings(&["jack", "a| b||c"]), strings(&["jane", "solo"])];
+        flatten_column(&names, &mut rows, "tags", '|').unwrap();
+        assert_eq!(rows[0][1], "a, b, c");
+        assert_eq!(rows[1][1], "solo");
+        assert!(flatten_column(&names, &mut rows, "bogus", '|').is_err());
+    }
+
+    #[test]
+    fn test_sample_rows() {
+        let all: Vec<Vec<String>> = (0..100).map(|i| vec![i.to_string()]).collect();
+        let mut rows = all.clone();
+        let kept = sample_rows(&mut rows, 5, 42);
+        assert_eq!(kept, vec![3, 18, 61, 75, 84]);
+        for (row, &i) in rows.iter().zip(&kept) {
+            assert_eq!(row, &all[i]);
+        }
+
+        let mut again = all.clone();
+        assert_eq!(sample_rows(&mut again, 5, 42), kept);
+        assert_eq!(again, rows);
+        let mut other = all.clone();
+        assert_ne!(sample_rows(&mut other, 5, 7), kept);
+
+        let mut few = all[..3].to_vec();
+        assert_eq!(sample_rows(&mut few, 5, 42), vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn test_histogram() {
+        let mut names = strings(&["name", "team"]);
+        let mut rows = vec![
+            strings(&["jack", "red"]),
+            strings(&["jane", "blue"]),
+            strings(&["erin", "blue"]),
+            strings(&["finn", "green"]),
+            strings(&["kim", "red"]),
+            strings(&["lee", "blue"]),
+        ];
+        histogram(&mut names, &mut rows, "team").unwrap();
+        assert_eq!(names, vec!["team", "count"]);
+        assert_eq!(
+            rows,
+            vec![
+                strings(&["blue", "3"]),
+                strings(&["red", "2"]),
+                strings(&["green", "1"])
+            ]
+        );
+        assert!(histogram(&mut names, &mut rows, "bogus").is_err());
+    }
+
+    #[test]
+    fn test_sanitize_rows() {
+        let mut rows = vec![strings(&["\x1b[2J\x1b[31mjack\x1b[0m", "multi\nline"])];
+        sanitize_rows(&mut rows);
+        assert_eq!(rows, vec![strings(&["jack", "multi\nline"])]);
+    }
+
+    #[test]
+    fn test_group_by() {
+        let names = strings(&["team", "name"]);
+        let mut rows = vec![
+            strings(&["red", "jack"]),
+            strings(&["blue", "jane"]),
+            strings(&["red", "erin"]),
+        ];
+        assert_eq!(group_by(&names, &mut rows, "team").unwrap(), vec![0, 2, 1]);
+        let order: Vec<&str> = rows.iter().map(|row| row[1].as_str()).collect();
+        assert_eq!(order, vec!["jack", "erin", "jane"]);
+        assert!(group_by(&names, &mut rows, "bogus").is_err());
+    }
+
+    #[test]
+    fn test_ditto_columns() {
+        let names = strings(&["team", "name"]);
+        let mut rows = vec![
+            strings(&["red", "jack"]),
+            strings(&["red", "jane"]),
+            strings(&["red", "jane"]),
+            strings(&["blue", "erin"]),
+            strings(&["red", "finn"]),
+        ];
+        ditto_columns(&names, &mut rows, &["team"], "\"").unwrap();
+        let teams: Vec<&str> = rows.iter().map(|row| row[0].as_str()).collect();
+        assert_eq!(teams, vec!["red", "\"", "\"", "blue", "red"]);
+        assert_eq!(rows[2][1], "jane");
+        assert!(ditto_columns(&names, &mut rows, &["bogus"], "").is_err());
+    }
+
+    #[test]
+    fn test_check_field_counts() {
+        let records = vec![
+            (1, strings(&["jack", "35"])),
+            (3, strings(&["jane"])),
+            (4, strings(&["erin", "20", "extra"])),
+        ];
+        match check_field_counts(&records, 2) {
+            Err(TabbsError::Parse { line, message }) => {
+                assert_eq!(line, 3);
+                assert_eq!(message, "expected 2 fields, found 1");
+            }
+            other => panic!("unexpected result: {:?}", other),
+        }
+        assert!(check_field_counts(&records[..1], 2).is_ok());
+    }
+}
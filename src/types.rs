@@ -0,0 +1,111 @@
+//! Detection of the data type held by each column.
+
+use crate::error::TabbsError;
+use std::fmt;
+use std::str::FromStr;
+
+/// The type of the values in a column.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColumnType {
+    /// Whole numbers such as `35` or `-2`.
+    Integer,
+    /// Decimal numbers such as `3.5`.
+    Float,
+    /// Boolean words such as `true` or `no`.
+    Boolean,
+    /// Anything else.
+    Text,
+}
+
+impl ColumnType {
+    /// Whether the type holds numbers.
+    pub fn is_numeric(self) -> bool {
+        matches!(self, ColumnType::Integer | ColumnType::Float)
+    }
+}
+
+impl fmt::Display for ColumnType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            ColumnType::Integer => "int",
+            ColumnType::Float => "float",
+            ColumnType::Boolean => "bool",
+            ColumnType::Text => "str",
+        };
+        f.write_str(name)
+    }
+}
+
+impl FromStr for ColumnType {
+    type Err = TabbsError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "int" => Ok(ColumnType::Integer),
+            "float" => Ok(ColumnType::Float),
+            "bool" => Ok(ColumnType::Boolean),
+            "str" => Ok(ColumnType::Text),
+            _ => Err(TabbsError::Config(format!("Invalid column type: {}", s))),
+        }
+    }
+}
+
+/// Return the narrowest type that `value` can be read as.
+pub fn detect_type(value: &str) -> ColumnType {
+    let value = value.trim();
+    if value.parse::<i64>().is_ok() {
+        ColumnType::Integer
+    } else if value.parse::<f64>().is_ok_and(f64::is_finite) {
+        ColumnType::Float
+    } else if parse_bool(value).is_some() {
+        ColumnType::Boolean
+    } else {
+        ColumnType::Text
+    }
+}
+
+/// Read `true`/`false` or `yes`/`no` in any case as a boolean.
+pub fn parse_bool(value: &str) -> Option<bool> {
+    match value.trim().to_lowercase().as_str() {
+        "true" | "yes" => Some(true),
+        "false" | "no" => Some(false),
+        _ => None,
+    }
+}
+
+/// Return the type that fits every non-empty value, or `Text` if there are none.
+pub fn detect_column_type<'a>(values: impl IntoIterator<Item = &'a str>) -> ColumnType {
+    let mut detected = None;
+    for value in values.into_iter().filter(|value| !value.trim().is_empty()) {
+        detected = Some(match (detected, detect_type(value)) {
+            (None, kind) => kind,
+            (Some(a), b) if a == b => a,
+            (Some(ColumnType::Integer), ColumnType::Float)
+            | (Some(ColumnType::Float), ColumnType::Integer) => ColumnType::Float,
+            _ => return ColumnType::Text,
+        });
+    }
+    detected.unwrap_or(ColumnType::Text)
+}
+
+/// Detect the type of each of the first `column_count` columns of `rows`.
+pub fn detect_column_types(column_count: usize, rows: &[Vec<String>]) -> Vec<ColumnType> {
+    (0..column_count)
+        .map(|i| detect_column_type(rows.iter().filter_map(|row| row.get(i).map(|s| s.as_str()))))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_column_type() {
+        assert_eq!(detect_column_type(["35", "", "-2"]), ColumnType::Integer);
+        assert_eq!(detect_column_type(["35", "3.5"]), ColumnType::Float);
+        assert_eq!(detect_column_type(["yes", "False"]), ColumnType::Boolean);
+        assert_eq!(detect_column_type(["35", "yes"]), ColumnType::Text);
+        assert_eq!(detect_column_type(["nan", "inf"]), ColumnType::Text);
+        assert_eq!(detect_column_type([""]), ColumnType::Text);
+    }
+}